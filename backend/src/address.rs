@@ -0,0 +1,145 @@
+//! Canonical Sui addresses and object IDs.
+//!
+//! Sui accepts the same 32-byte value written many ways (`0x2`, `0X02`, upper-case hex, no
+//! prefix). Everything we store or compare goes through these types, which always hold the
+//! long form: `0x` followed by 64 lower-case hex digits.
+
+use std::fmt;
+use std::str::FromStr;
+use sea_orm::DeriveValueType;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+#[derive(Debug, thiserror::Error)]
+#[error("Invalid Sui address or object ID: {0}")]
+pub struct AddressError(String);
+
+/// Pads `s` to the canonical long form, rejecting anything that isn't at most 32 bytes of hex.
+pub fn normalize(s: &str) -> Result<String, AddressError> {
+    let hex = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")).unwrap_or(s);
+    if hex.is_empty() || hex.len() > 64 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(AddressError(s.to_string()));
+    }
+    Ok(format!("0x{:0>64}", hex.to_ascii_lowercase()))
+}
+
+macro_rules! sui_id {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, DeriveValueType)]
+        pub struct $name(String);
+
+        impl $name {
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+
+            pub fn to_bytes(&self) -> [u8; 32] {
+                let mut bytes = [0u8; 32];
+                hex::decode_to_slice(&self.0[2..], &mut bytes)
+                    .expect("normalized IDs are always 64 hex digits");
+                bytes
+            }
+        }
+
+        // Required of primary-key columns; these keys never come from an auto-increment.
+        impl sea_orm::TryFromU64 for $name {
+            fn try_from_u64(_: u64) -> Result<Self, sea_orm::DbErr> {
+                Err(sea_orm::DbErr::ConvertFromU64(stringify!($name)))
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = AddressError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                normalize(s).map(Self)
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(&self.0)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let s = String::deserialize(deserializer)?;
+                s.parse().map_err(de::Error::custom)
+            }
+        }
+    };
+}
+
+sui_id! {
+    /// An account address: envelope owners, claimers, transaction senders.
+    SuiAddress
+}
+
+sui_id! {
+    /// The ID of an on-chain object such as an `Envelope`.
+    ObjectId
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TWO: &str = "0x0000000000000000000000000000000000000000000000000000000000000002";
+
+    #[test]
+    fn accepts_either_prefix_or_none() {
+        assert_eq!(normalize("0x2").unwrap(), TWO);
+        assert_eq!(normalize("0X2").unwrap(), TWO);
+        assert_eq!(normalize("2").unwrap(), TWO);
+    }
+
+    #[test]
+    fn pads_short_addresses() {
+        assert_eq!(normalize("0x02").unwrap(), TWO);
+        assert_eq!(normalize("0xabc").unwrap(), format!("0x{}abc", "0".repeat(61)));
+        assert_eq!(normalize(TWO).unwrap(), TWO);
+    }
+
+    #[test]
+    fn lowercases() {
+        let upper = format!("0x{}", "AB".repeat(32));
+        assert_eq!(normalize(&upper).unwrap(), format!("0x{}", "ab".repeat(32)));
+        assert_eq!(normalize("0XaBc").unwrap(), normalize("0xabc").unwrap());
+    }
+
+    #[test]
+    fn rejects_more_than_64_digits() {
+        assert!(normalize(&format!("0x{}", "1".repeat(64))).is_ok());
+        assert!(normalize(&format!("0x{}", "1".repeat(65))).is_err());
+        assert!(normalize(&format!("0x0{}", "1".repeat(64))).is_err());
+    }
+
+    #[test]
+    fn rejects_empty_input() {
+        assert!(normalize("").is_err());
+        assert!(normalize("0x").is_err());
+        assert!(normalize("0X").is_err());
+    }
+
+    #[test]
+    fn rejects_non_hex_input() {
+        for input in ["0xg", "0x2::sui", "xyz", "0x 2", " 0x2", "0x0x2", "0x２", "0xé"] {
+            assert!(normalize(input).is_err(), "{:?} should not normalize", input);
+        }
+    }
+
+    #[test]
+    fn ids_parse_and_print_the_long_form() {
+        let id: ObjectId = "0X2".parse().unwrap();
+        assert_eq!(id.to_string(), TWO);
+        assert_eq!(id.to_bytes()[31], 2);
+        assert_eq!(id, TWO.parse().unwrap());
+    }
+}
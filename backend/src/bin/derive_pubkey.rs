@@ -1,5 +1,4 @@
 use ed25519_dalek::SigningKey;
-use hex;
 
 fn main() {
     let secret_hex = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
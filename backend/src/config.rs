@@ -1,12 +1,46 @@
 use std::env;
+use std::str::FromStr;
+use std::time::Duration;
 use dotenvy::dotenv;
 
+use crate::address;
+
+/// How the indexer receives events for a network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IngestionMode {
+    /// `suix_subscribeEvent` notifications, backfilled on every reconnect.
+    Websocket,
+    /// `suix_queryEvents` on an interval from the saved cursor.
+    Poll,
+    /// Both at once: the socket for latency, the poller to fill any gaps.
+    Both,
+}
+
+impl FromStr for IngestionMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "websocket" | "ws" => Ok(Self::Websocket),
+            "poll" => Ok(Self::Poll),
+            "both" => Ok(Self::Both),
+            other => Err(format!("Unknown ingestion mode: {}", other)),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct NetworkConfig {
     pub name: String,
-    pub rpc_url: String,
-    pub ws_url: String,
+    /// RPC endpoints in order of preference; calls fail over between them.
+    pub rpc_urls: Vec<String>,
+    /// WebSocket endpoints, the healthiest tried on each reconnect; empty when the network is
+    /// only polled.
+    pub ws_urls: Vec<String>,
+    /// Long form, see [`crate::address`].
     pub package_id: String,
+    pub ingestion_mode: IngestionMode,
+    pub poll_interval: Duration,
 }
 
 #[derive(Debug, Clone)]
@@ -15,7 +49,35 @@ pub struct Config {
     pub networks: Vec<NetworkConfig>,
     pub server_host: String,
     pub server_port: String,
-    pub enable_websocket: bool,
+    /// How often active envelopes are re-read from chain; `None` disables the reconciler.
+    pub reconcile_interval: Option<Duration>,
+    /// Bearer token for `/api/admin`; the admin API is disabled when unset.
+    pub admin_token: Option<Secret>,
+    /// Bearer token required by the sync endpoints (the admin token works too); they are
+    /// public when unset.
+    pub sync_api_key: Option<Secret>,
+    /// Sync requests each client IP may make per minute; `None` disables the limit.
+    pub sync_rate_per_minute: Option<u32>,
+    /// Sync requests a client IP may make in a burst before the per-minute rate applies.
+    pub sync_rate_burst: u32,
+    /// Syncs processed at once across all clients; further ones are rejected until one finishes.
+    pub max_concurrent_syncs: usize,
+}
+
+/// A credential that is left out of `Debug` output, since the config is logged at startup.
+#[derive(Clone)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Debug for Secret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Secret(***)")
+    }
 }
 
 impl Config {
@@ -25,42 +87,80 @@ impl Config {
         let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
         let server_host = env::var("SERVER_HOST").unwrap_or_else(|_| "127.0.0.1".to_string());
         let server_port = env::var("SERVER_PORT").unwrap_or_else(|_| "3000".to_string());
+        // Without WebSockets the indexer falls back to polling instead of being turned off.
         let enable_websocket = env::var("ENABLE_WEBSOCKET")
             .unwrap_or_else(|_| "true".to_string())
             .parse::<bool>()
             .unwrap_or(true);
+        let default_mode = if enable_websocket { IngestionMode::Websocket } else { IngestionMode::Poll };
+        let reconcile_interval = env::var("RECONCILE_INTERVAL_SECS")
+            .unwrap_or_else(|_| "600".to_string())
+            .parse::<u64>()
+            .ok()
+            .filter(|secs| *secs > 0)
+            .map(Duration::from_secs);
+        let admin_token = env::var("ADMIN_TOKEN").ok()
+            .filter(|t| !t.is_empty())
+            .map(Secret);
+        let sync_api_key = env::var("SYNC_API_KEY").ok()
+            .filter(|t| !t.is_empty())
+            .map(Secret);
+        let sync_rate_per_minute = env::var("SYNC_RATE_LIMIT_PER_MINUTE")
+            .unwrap_or_else(|_| "30".to_string())
+            .parse::<u32>()
+            .ok()
+            .filter(|rate| *rate > 0);
+        let sync_rate_burst = env::var("SYNC_RATE_LIMIT_BURST")
+            .ok()
+            .and_then(|v| v.parse::<u32>().ok())
+            .unwrap_or(10)
+            .max(1);
+        let max_concurrent_syncs = env::var("MAX_CONCURRENT_SYNCS")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(4)
+            .max(1);
         let active_network = env::var("ACTIVE_NETWORK").unwrap_or_else(|_| "all".to_string());
         
         let mut networks = Vec::new();
 
         // Testnet
-        if (active_network == "all" || active_network == "testnet") {
-            if let Ok(rpc) = env::var("TESTNET_RPC_URL") {
+        if active_network == "all" || active_network == "testnet" {
+            if let Some(rpc) = url_list("TESTNET_RPC_URL") {
+                let mode = ingestion_mode("TESTNET", default_mode);
                 networks.push(NetworkConfig {
                     name: "testnet".to_string(),
-                    rpc_url: rpc,
-                    ws_url: env::var("TESTNET_WS_URL").expect("TESTNET_WS_URL must be set"),
-                    package_id: env::var("TESTNET_PACKAGE_ID").expect("TESTNET_PACKAGE_ID must be set"),
+                    rpc_urls: rpc,
+                    ws_urls: ws_urls("TESTNET", mode),
+                    package_id: package_id("TESTNET"),
+                    ingestion_mode: mode,
+                    poll_interval: poll_interval("TESTNET"),
                 });
-            } else if let Ok(rpc) = env::var("SUI_RPC_URL") {
+            } else if let Some(rpc) = url_list("SUI_RPC_URL") {
                 // Backward compatibility
+                let mode = ingestion_mode("SUI", default_mode);
                 networks.push(NetworkConfig {
                     name: "testnet".to_string(),
-                    rpc_url: rpc,
-                    ws_url: env::var("SUI_WS_URL").expect("SUI_WS_URL must be set"),
-                    package_id: env::var("SUI_PACKAGE_ID").expect("SUI_PACKAGE_ID must be set"),
+                    rpc_urls: rpc,
+                    ws_urls: ws_urls("SUI", mode),
+                    package_id: package_id("SUI"),
+                    ingestion_mode: mode,
+                    poll_interval: poll_interval("SUI"),
                 });
             }
         }
 
         // Mainnet
-        if (active_network == "all" || active_network == "mainnet") {
-            if let Ok(rpc) = env::var("MAINNET_RPC_URL") {
+        if active_network == "all" || active_network == "mainnet" {
+            if let Some(rpc) = url_list("MAINNET_RPC_URL") {
+                let mode = ingestion_mode("MAINNET", default_mode);
                 networks.push(NetworkConfig {
                     name: "mainnet".to_string(),
-                    rpc_url: rpc,
-                    ws_url: env::var("MAINNET_WS_URL").expect("MAINNET_WS_URL must be set"),
-                    package_id: env::var("MAINNET_PACKAGE_ID").expect("MAINNET_PACKAGE_ID must be set"),
+                    rpc_urls: rpc,
+                    ws_urls: ws_urls("MAINNET", mode),
+                    package_id: package_id("MAINNET"),
+                    ingestion_mode: mode,
+                    poll_interval: poll_interval("MAINNET"),
                 });
             }
         }
@@ -70,7 +170,61 @@ impl Config {
             networks,
             server_host,
             server_port,
-            enable_websocket,
+            reconcile_interval,
+            admin_token,
+            sync_api_key,
+            sync_rate_per_minute,
+            sync_rate_burst,
+            max_concurrent_syncs,
         }
     }
+
+    /// The configured network called `name`.
+    pub fn network(&self, name: &str) -> Option<&NetworkConfig> {
+        self.networks.iter().find(|n| n.name == name)
+    }
+}
+
+/// Comma-separated URLs from `var`; `None` when unset or empty.
+fn url_list(var: &str) -> Option<Vec<String>> {
+    let urls: Vec<String> = env::var(var).ok()?
+        .split(',')
+        .map(|u| u.trim().to_string())
+        .filter(|u| !u.is_empty())
+        .collect();
+    (!urls.is_empty()).then_some(urls)
+}
+
+/// `<PREFIX>_INGESTION_MODE`: `websocket`, `poll` or `both`.
+fn ingestion_mode(prefix: &str, default: IngestionMode) -> IngestionMode {
+    match env::var(format!("{}_INGESTION_MODE", prefix)) {
+        Ok(mode) => mode.parse().unwrap_or_else(|e| panic!("{}_INGESTION_MODE: {}", prefix, e)),
+        Err(_) => default,
+    }
+}
+
+/// `<PREFIX>_PACKAGE_ID` in its long form, which is how event and object types spell it.
+fn package_id(prefix: &str) -> String {
+    let var = format!("{}_PACKAGE_ID", prefix);
+    let id = env::var(&var).unwrap_or_else(|_| panic!("{} must be set", var));
+    address::normalize(&id).unwrap_or_else(|e| panic!("{}: {}", var, e))
+}
+
+/// `<PREFIX>_WS_URL`, which only a network that is just polled may leave unset.
+fn ws_urls(prefix: &str, mode: IngestionMode) -> Vec<String> {
+    match url_list(&format!("{}_WS_URL", prefix)) {
+        Some(urls) => urls,
+        None if mode == IngestionMode::Poll => Vec::new(),
+        None => panic!("{}_WS_URL must be set unless {}_INGESTION_MODE is poll", prefix, prefix),
+    }
+}
+
+/// `<PREFIX>_POLL_INTERVAL_MS`, defaulting to 5 seconds.
+fn poll_interval(prefix: &str) -> Duration {
+    let ms = env::var(format!("{}_POLL_INTERVAL_MS", prefix))
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(5000);
+    assert!(ms > 0, "{}_POLL_INTERVAL_MS must be greater than 0", prefix);
+    Duration::from_millis(ms)
 }
@@ -0,0 +1,136 @@
+use axum::{
+    extract::{Request, State},
+    http::header,
+    middleware::Next,
+    response::Response,
+};
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use crate::AppState;
+use crate::error::ApiError;
+use crate::extract::{Json, Path, Query};
+use crate::models::dead_letter_events;
+use crate::services::sui_indexer;
+
+const DEFAULT_LIST_LIMIT: u64 = 100;
+const MAX_LIST_LIMIT: u64 = 500;
+
+/// Rejects admin requests unless they carry `Authorization: Bearer <ADMIN_TOKEN>`.
+/// Without a configured token the admin API is switched off entirely.
+pub async fn require_admin(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let Some(token) = &state.config.admin_token else {
+        return Err(ApiError::AdminDisabled);
+    };
+
+    if bearer_token(&request) != Some(token.expose()) {
+        return Err(ApiError::Unauthorized);
+    }
+
+    Ok(next.run(request).await)
+}
+
+/// The token of an `Authorization: Bearer <token>` header.
+pub fn bearer_token(request: &Request) -> Option<&str> {
+    request.headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+}
+
+#[derive(Deserialize)]
+pub struct DeadLetterQuery {
+    pub network: Option<String>,
+    pub limit: Option<u64>,
+}
+
+#[derive(Serialize)]
+pub struct ReplayFailure {
+    pub id: i64,
+    pub error: String,
+}
+
+#[derive(Serialize)]
+pub struct ReplayReport {
+    pub replayed: usize,
+    pub failed: Vec<ReplayFailure>,
+}
+
+pub async fn list_dead_letters(
+    State(state): State<AppState>,
+    Query(query): Query<DeadLetterQuery>,
+) -> Result<Json<Vec<dead_letter_events::Model>>, ApiError> {
+    let mut find = dead_letter_events::Entity::find();
+
+    if let Some(network) = query.network {
+        find = find.filter(dead_letter_events::Column::Network.eq(network));
+    }
+
+    let dead_letters = find
+        .order_by_desc(dead_letter_events::Column::Id)
+        .limit(query.limit.unwrap_or(DEFAULT_LIST_LIMIT).min(MAX_LIST_LIMIT))
+        .all(&state.db)
+        .await?;
+
+    Ok(Json(dead_letters))
+}
+
+pub async fn replay_dead_letter(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<Json<ReplayReport>, ApiError> {
+    let dead_letter = dead_letter_events::Entity::find_by_id(id)
+        .one(&state.db)
+        .await?
+        .ok_or(ApiError::DeadLetterNotFound(id))?;
+
+    let (Some(network), Some(rpc)) = (state.config.network(&dead_letter.network), state.rpc.get(&dead_letter.network)) else {
+        return Err(ApiError::NetworkNotConfigured(dead_letter.network.clone()));
+    };
+
+    sui_indexer::replay_dead_letter(&state.db, &network.package_id, rpc, dead_letter)
+        .await
+        .map_err(ApiError::replay_failed)?;
+
+    Ok(Json(ReplayReport { replayed: 1, failed: Vec::new() }))
+}
+
+/// Replays every dead letter (optionally only one network's), oldest first.
+pub async fn replay_all(
+    State(state): State<AppState>,
+    Query(query): Query<DeadLetterQuery>,
+) -> Result<Json<ReplayReport>, ApiError> {
+    let mut find = dead_letter_events::Entity::find();
+
+    if let Some(network) = query.network {
+        find = find.filter(dead_letter_events::Column::Network.eq(network));
+    }
+
+    let dead_letters = find
+        .order_by_asc(dead_letter_events::Column::Id)
+        .all(&state.db)
+        .await?;
+
+    let mut report = ReplayReport { replayed: 0, failed: Vec::new() };
+
+    for dead_letter in dead_letters {
+        let id = dead_letter.id;
+        let (Some(network), Some(rpc)) = (state.config.network(&dead_letter.network), state.rpc.get(&dead_letter.network)) else {
+            report.failed.push(ReplayFailure {
+                id,
+                error: ApiError::NetworkNotConfigured(dead_letter.network.clone()).to_string(),
+            });
+            continue;
+        };
+
+        match sui_indexer::replay_dead_letter(&state.db, &network.package_id, rpc, dead_letter).await {
+            Ok(()) => report.replayed += 1,
+            Err(e) => report.failed.push(ReplayFailure { id, error: e.to_string() }),
+        }
+    }
+
+    Ok(Json(report))
+}
@@ -1,110 +1,305 @@
-use axum::{
-    extract::{Path, Query, State},
-    Json,
-    http::StatusCode,
-};
+use axum::extract::State;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use rust_decimal::Decimal;
 use sea_orm::*;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::str::FromStr;
 use crate::AppState;
-use crate::models::{envelopes, claims};
+use crate::address::{ObjectId, SuiAddress};
+use crate::error::ApiError;
+use crate::extract::{Json, Path, Query};
+use crate::models::{envelopes, claims, coins, refunds};
 use crate::services::sui_indexer;
+use crate::type_tag::TypeTag;
+
+const DEFAULT_PAGE_SIZE: u64 = 20;
+const MAX_PAGE_SIZE: u64 = 100;
+const MAX_BULK_SYNC_IDS: usize = 50;
 
 #[derive(Deserialize)]
 pub struct GeneralQuery {
-    pub address: Option<String>,
+    pub address: Option<SuiAddress>,
     pub network: Option<String>,
+    /// Page size; defaults to 20 and is capped at 100.
+    pub limit: Option<u64>,
+    /// `next_cursor` from the previous page.
+    pub cursor: Option<String>,
+    /// `desc` (newest first, the default) or `asc`.
+    pub sort: Option<String>,
+    // Envelope filters, used by `list_active` and `list_created`.
+    pub coin_type: Option<TypeTag>,
+    pub mode: Option<EnvelopeMode>,
+    pub requires_verification: Option<bool>,
+    /// Bounds on `total_amount` in the coin's smallest unit, inclusive.
+    pub min_amount: Option<Decimal>,
+    pub max_amount: Option<Decimal>,
+    pub min_remaining: Option<i64>,
+    /// RFC 3339 bounds on `created_at`, inclusive.
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+}
+
+/// Distribution mode, as the `MODE_*` constants in the Move module.
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum EnvelopeMode {
+    Random,
+    Equal,
+}
+
+impl EnvelopeMode {
+    pub fn as_i16(self) -> i16 {
+        match self {
+            Self::Random => 0,
+            Self::Equal => 1,
+        }
+    }
 }
 
+/// One page of a listing. `next_cursor` is `None` on the last page.
 #[derive(Serialize)]
-pub struct EnvelopeWithClaim {
+pub struct Paginated<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+}
+
+impl<T> Paginated<T> {
+    fn map<U>(self, f: impl FnMut(T) -> U) -> Paginated<U> {
+        Paginated { items: self.items.into_iter().map(f).collect(), next_cursor: self.next_cursor }
+    }
+}
+
+/// Display metadata from the `coins` registry.
+#[derive(Serialize)]
+pub struct CoinInfo {
+    pub name: String,
+    pub symbol: String,
+    pub decimals: i16,
+    pub icon_url: Option<String>,
+}
+
+/// An envelope row plus its coin. `coin` and `formatted_amount` are `None` until the
+/// indexer has fetched the coin's metadata.
+#[derive(Serialize)]
+pub struct EnvelopeView {
+    #[serde(flatten)]
     pub envelope: envelopes::Model,
+    pub coin: Option<CoinInfo>,
+    /// `total_amount` in whole coins, e.g. "1.5".
+    pub formatted_amount: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ClaimView {
+    #[serde(flatten)]
     pub claim: claims::Model,
+    /// `amount` in whole coins.
+    pub formatted_amount: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct EnvelopeWithClaim {
+    pub envelope: EnvelopeView,
+    pub claim: ClaimView,
 }
 
 #[derive(Serialize)]
 pub struct EnvelopeDetail {
-    pub envelope: envelopes::Model,
-    pub claims: Vec<claims::Model>,
+    pub envelope: EnvelopeView,
+    pub claims: Vec<ClaimView>,
+}
+
+/// Either explicit ids, or an owner whose indexed envelopes should all be refreshed. Owners are
+/// synced [`MAX_BULK_SYNC_IDS`] envelopes per request; pass the returned `next_cursor` back as
+/// `cursor` for the rest.
+#[derive(Deserialize)]
+pub struct BulkSyncRequest {
+    pub ids: Option<Vec<ObjectId>>,
+    pub owner: Option<SuiAddress>,
+    pub cursor: Option<ObjectId>,
+}
+
+#[derive(Serialize)]
+pub struct BulkSyncResult {
+    pub id: ObjectId,
+    #[serde(flatten)]
+    pub outcome: SyncOutcome,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum SyncOutcome {
+    Synced { claims_added: usize },
+    Failed { error: String },
+}
+
+/// Amounts are in the coin's smallest unit; durations in seconds from `created_at`.
+#[derive(Serialize)]
+pub struct EnvelopeStats {
+    pub envelope_id: ObjectId,
+    pub network: String,
+    pub claim_count: usize,
+    pub claimed_total: Decimal,
+    /// Still claimable: what is neither claimed nor withdrawn by the owner.
+    pub remaining_amount: Decimal,
+    pub refunded_amount: Decimal,
+    pub min_claim: Option<Decimal>,
+    pub max_claim: Option<Decimal>,
+    pub mean_claim: Option<Decimal>,
+    pub median_claim: Option<Decimal>,
+    /// The largest claim of a random-mode envelope; earliest wins a tie.
+    pub luckiest: Option<LuckiestClaim>,
+    pub time_to_first_claim: Option<i64>,
+    /// Set once every share has been claimed.
+    pub time_to_empty: Option<i64>,
+    /// Claims per minute, for minutes with at least one claim, oldest first.
+    pub claims_per_minute: Vec<MinuteBucket>,
+}
+
+#[derive(Serialize)]
+pub struct LuckiestClaim {
+    pub claimer: SuiAddress,
+    pub amount: Decimal,
+    pub tx_digest: String,
+}
+
+#[derive(Serialize)]
+pub struct MinuteBucket {
+    pub minute: NaiveDateTime,
+    pub count: usize,
 }
 
 pub async fn list_created(
     State(state): State<AppState>,
     Query(query): Query<GeneralQuery>,
-) -> Result<Json<Vec<envelopes::Model>>, (StatusCode, String)> {
-    let mut find = envelopes::Entity::find();
+) -> Result<Json<Paginated<EnvelopeView>>, ApiError> {
+    let (limit, order) = page_params(&query)?;
+    let mut find = envelopes::Entity::find()
+        .filter(envelope_filters(&query)?);
     
     if let Some(address) = query.address {
-        find = find.filter(envelopes::Column::Owner.eq(address.to_lowercase()));
+        find = find.filter(envelopes::Column::Owner.eq(address));
     }
     
     if let Some(network) = query.network {
         find = find.filter(envelopes::Column::Network.eq(network));
     }
 
+    if let Some(cursor) = &query.cursor {
+        let (created_at, envelope_id) = decode_cursor::<ObjectId>(cursor)?;
+        find = find.filter(after_cursor(
+            envelopes::Column::CreatedAt,
+            envelopes::Column::EnvelopeId,
+            created_at,
+            envelope_id,
+            &order,
+        ));
+    }
+
     let envelopes = find
-        .order_by_desc(envelopes::Column::CreatedAt)
+        .order_by(envelopes::Column::CreatedAt, order.clone())
+        .order_by(envelopes::Column::EnvelopeId, order)
+        .limit(limit + 1)
         .all(&state.db)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .await?;
 
-    Ok(Json(envelopes))
+    let coins = CoinRegistry::load(&state.db, envelopes.iter()).await?;
+    let page = paginate(envelopes, limit, |e| encode_cursor(e.created_at, &e.envelope_id));
+    Ok(Json(page.map(|e| coins.envelope(e))))
 }
 
 pub async fn list_active(
     State(state): State<AppState>,
     Query(query): Query<GeneralQuery>,
-) -> Result<Json<Vec<envelopes::Model>>, (StatusCode, String)> {
+) -> Result<Json<Paginated<EnvelopeView>>, ApiError> {
+    let (limit, order) = page_params(&query)?;
     let mut find = envelopes::Entity::find()
         .filter(envelopes::Column::RemainingCount.gt(0))
-        .filter(envelopes::Column::IsActive.eq(true));
+        .filter(envelopes::Column::IsActive.eq(true))
+        .filter(envelope_filters(&query)?);
     
     if let Some(network) = query.network {
         find = find.filter(envelopes::Column::Network.eq(network));
     }
 
+    if let Some(cursor) = &query.cursor {
+        let (created_at, envelope_id) = decode_cursor::<ObjectId>(cursor)?;
+        find = find.filter(after_cursor(
+            envelopes::Column::CreatedAt,
+            envelopes::Column::EnvelopeId,
+            created_at,
+            envelope_id,
+            &order,
+        ));
+    }
+
     let envelopes = find
-        .order_by_desc(envelopes::Column::CreatedAt)
+        .order_by(envelopes::Column::CreatedAt, order.clone())
+        .order_by(envelopes::Column::EnvelopeId, order)
+        .limit(limit + 1)
         .all(&state.db)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .await?;
 
-    Ok(Json(envelopes))
+    let coins = CoinRegistry::load(&state.db, envelopes.iter()).await?;
+    let page = paginate(envelopes, limit, |e| encode_cursor(e.created_at, &e.envelope_id));
+    Ok(Json(page.map(|e| coins.envelope(e))))
 }
 
 pub async fn list_claimed(
     State(state): State<AppState>,
     Query(query): Query<GeneralQuery>,
-) -> Result<Json<Vec<EnvelopeWithClaim>>, (StatusCode, String)> {
+) -> Result<Json<Paginated<EnvelopeWithClaim>>, ApiError> {
+    let (limit, order) = page_params(&query)?;
     let mut find = envelopes::Entity::find()
-        .find_also_related(claims::Entity);
+        .find_also_related(claims::Entity)
+        .filter(claims::Column::ClaimId.is_not_null());
     
     if let Some(address) = query.address {
-        find = find.filter(claims::Column::Claimer.eq(address.to_lowercase()));
+        find = find.filter(claims::Column::Claimer.eq(address));
     }
     
     if let Some(network) = query.network {
         find = find.filter(envelopes::Column::Network.eq(network));
     }
 
+    if let Some(cursor) = &query.cursor {
+        let (claimed_at, claim_id) = decode_cursor::<i64>(cursor)?;
+        find = find.filter(after_cursor(
+            claims::Column::ClaimedAt,
+            claims::Column::ClaimId,
+            claimed_at,
+            claim_id,
+            &order,
+        ));
+    }
+
     let claimed = find
-        .order_by_desc(claims::Column::ClaimedAt)
+        .order_by(claims::Column::ClaimedAt, order.clone())
+        .order_by(claims::Column::ClaimId, order)
+        .limit(limit + 1)
         .all(&state.db)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .await?;
 
-    let result = claimed.into_iter()
-        .filter_map(|(env, claim)| claim.map(|c| EnvelopeWithClaim { envelope: env, claim: c }))
+    let claimed: Vec<(envelopes::Model, claims::Model)> = claimed.into_iter()
+        .filter_map(|(env, claim)| claim.map(|c| (env, c)))
         .collect();
 
-    Ok(Json(result))
+    let coins = CoinRegistry::load(&state.db, claimed.iter().map(|(env, _)| env)).await?;
+    let page = paginate(claimed, limit, |(_, c)| encode_cursor(c.claimed_at, &c.claim_id));
+    Ok(Json(page.map(|(env, claim)| EnvelopeWithClaim {
+        claim: coins.claim(claim, &env.coin_type),
+        envelope: coins.envelope(env),
+    })))
 }
 
 pub async fn get_details(
     State(state): State<AppState>,
-    Path(id): Path<String>,
+    Path(id): Path<ObjectId>,
     Query(query): Query<GeneralQuery>,
-) -> Result<Json<EnvelopeDetail>, (StatusCode, String)> {
+) -> Result<Json<EnvelopeDetail>, ApiError> {
     let mut find = envelopes::Entity::find()
         .filter(envelopes::Column::EnvelopeId.eq(id.clone()));
     
@@ -114,9 +309,8 @@ pub async fn get_details(
 
     let envelope = find
         .one(&state.db)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
-        .ok_or((StatusCode::NOT_FOUND, "Envelope not found".to_string()))?;
+        .await?
+        .ok_or_else(|| ApiError::EnvelopeNotFound(id.clone()))?;
 
     let mut find_claims = claims::Entity::find()
         .filter(claims::Column::EnvelopeId.eq(id));
@@ -128,49 +322,523 @@ pub async fn get_details(
     let claims = find_claims
         .order_by_desc(claims::Column::ClaimedAt)
         .all(&state.db)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .await?;
+
+    let coins = CoinRegistry::load(&state.db, std::iter::once(&envelope)).await?;
 
     Ok(Json(EnvelopeDetail {
-        envelope,
-        claims,
+        claims: claims.into_iter().map(|c| coins.claim(c, &envelope.coin_type)).collect(),
+        envelope: coins.envelope(envelope),
     }))
 }
 
+pub async fn get_stats(
+    State(state): State<AppState>,
+    Path(id): Path<ObjectId>,
+    Query(query): Query<GeneralQuery>,
+) -> Result<Json<EnvelopeStats>, ApiError> {
+    let mut find = envelopes::Entity::find()
+        .filter(envelopes::Column::EnvelopeId.eq(id.clone()));
+
+    if let Some(ref network) = query.network {
+        find = find.filter(envelopes::Column::Network.eq(network));
+    }
+
+    let envelope = find
+        .one(&state.db)
+        .await?
+        .ok_or_else(|| ApiError::EnvelopeNotFound(id.clone()))?;
+
+    let claims = claims::Entity::find()
+        .filter(claims::Column::EnvelopeId.eq(id.clone()))
+        .filter(claims::Column::Network.eq(envelope.network.as_str()))
+        .order_by_asc(claims::Column::ClaimedAt)
+        .order_by_asc(claims::Column::ClaimId)
+        .all(&state.db)
+        .await?;
+
+    let refunded_amount: Decimal = refunds::Entity::find()
+        .filter(refunds::Column::EnvelopeId.eq(id))
+        .filter(refunds::Column::Network.eq(envelope.network.as_str()))
+        .all(&state.db)
+        .await?
+        .iter()
+        .map(|r| r.amount)
+        .sum();
+
+    Ok(Json(envelope_stats(envelope, &claims, refunded_amount)))
+}
+
+fn envelope_stats(envelope: envelopes::Model, claims: &[claims::Model], refunded_amount: Decimal) -> EnvelopeStats {
+    let claimed_total: Decimal = claims.iter().map(|c| c.amount).sum();
+
+    let mut amounts: Vec<Decimal> = claims.iter().map(|c| c.amount).collect();
+    amounts.sort();
+    let median_claim = match amounts.len() {
+        0 => None,
+        n if n % 2 == 1 => Some(amounts[n / 2]),
+        n => Some((amounts[n / 2 - 1] + amounts[n / 2]) / Decimal::TWO),
+    };
+    let mean_claim = (!claims.is_empty())
+        .then(|| (claimed_total / Decimal::from(claims.len())).round_dp(2));
+
+    // In equal mode every share is the same, so nobody is luckiest.
+    let luckiest = (envelope.mode == EnvelopeMode::Random.as_i16())
+        .then(|| claims.iter().rev().max_by_key(|c| c.amount))
+        .flatten()
+        .map(|c| LuckiestClaim {
+            claimer: c.claimer.clone(),
+            amount: c.amount,
+            tx_digest: c.tx_digest.clone(),
+        });
+
+    let time_to_first_claim = claims.first()
+        .map(|c| (c.claimed_at - envelope.created_at).num_seconds());
+    let time_to_empty = (claims.len() as i64 >= envelope.total_count)
+        .then(|| claims.last())
+        .flatten()
+        .map(|c| (c.claimed_at - envelope.created_at).num_seconds());
+
+    let mut claims_per_minute: Vec<MinuteBucket> = Vec::new();
+    for claim in claims {
+        let ts = claim.claimed_at.and_utc().timestamp();
+        let minute = DateTime::from_timestamp(ts - ts.rem_euclid(60), 0)
+            .map(|dt| dt.naive_utc())
+            .unwrap_or(claim.claimed_at);
+        match claims_per_minute.last_mut() {
+            Some(bucket) if bucket.minute == minute => bucket.count += 1,
+            _ => claims_per_minute.push(MinuteBucket { minute, count: 1 }),
+        }
+    }
+
+    EnvelopeStats {
+        remaining_amount: (envelope.total_amount - claimed_total - refunded_amount).max(Decimal::ZERO),
+        envelope_id: envelope.envelope_id,
+        network: envelope.network,
+        claim_count: claims.len(),
+        claimed_total,
+        refunded_amount,
+        min_claim: amounts.first().copied(),
+        max_claim: amounts.last().copied(),
+        mean_claim,
+        median_claim,
+        luckiest,
+        time_to_first_claim,
+        time_to_empty,
+        claims_per_minute,
+    }
+}
+
 pub async fn sync_envelope(
     State(state): State<AppState>,
-    Path(id): Path<String>,
+    Path(id): Path<ObjectId>,
     Query(query): Query<GeneralQuery>,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+) -> Result<Json<serde_json::Value>, ApiError> {
     let network = query.network.unwrap_or_else(|| "testnet".to_string());
     
-    // Find RPC URL for the network
-    let network_conf = state.config.networks.iter()
-        .find(|n| n.name == network)
-        .ok_or((StatusCode::BAD_REQUEST, format!("Network {} not configured", network)))?;
+    // Find the RPC client for the network
+    let rpc = state.rpc.get(&network)
+        .ok_or_else(|| ApiError::NetworkNotConfigured(network.clone()))?;
+    let package_id = &state.config.network(&network)
+        .ok_or_else(|| ApiError::NetworkNotConfigured(network.clone()))?
+        .package_id;
 
-    sui_indexer::sync_envelope_by_id(&state.db, &network, &network_conf.rpc_url, &id)
+    let sync = sui_indexer::sync_envelope_by_id(&state.db, &network, package_id, rpc, &id)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(ApiError::sync_failed)?;
 
-    Ok(Json(json!({ "status": "success", "message": "Envelope synced" })))
+    Ok(Json(json!({
+        "status": "success",
+        "message": "Envelope synced",
+        "claims_added": sync.claims_added,
+    })))
+}
+
+pub async fn sync_envelopes(
+    State(state): State<AppState>,
+    Query(query): Query<GeneralQuery>,
+    Json(request): Json<BulkSyncRequest>,
+) -> Result<Json<Paginated<BulkSyncResult>>, ApiError> {
+    let network = query.network.unwrap_or_else(|| "testnet".to_string());
+
+    let rpc = state.rpc.get(&network)
+        .ok_or_else(|| ApiError::NetworkNotConfigured(network.clone()))?;
+    let package_id = &state.config.network(&network)
+        .ok_or_else(|| ApiError::NetworkNotConfigured(network.clone()))?
+        .package_id;
+
+    let page = match (request.ids, request.owner) {
+        (Some(mut ids), None) => {
+            if ids.is_empty() || ids.len() > MAX_BULK_SYNC_IDS {
+                return Err(ApiError::InvalidParameter(format!(
+                    "ids must contain between 1 and {} envelope ids",
+                    MAX_BULK_SYNC_IDS
+                )));
+            }
+            if request.cursor.is_some() {
+                return Err(ApiError::InvalidParameter("cursor only applies to owner".to_string()));
+            }
+            ids.sort();
+            ids.dedup();
+            Paginated { items: ids, next_cursor: None }
+        }
+        (None, Some(owner)) => {
+            let mut find = envelopes::Entity::find()
+                .select_only()
+                .column(envelopes::Column::EnvelopeId)
+                .filter(envelopes::Column::Network.eq(network.as_str()))
+                .filter(envelopes::Column::Owner.eq(owner));
+            if let Some(cursor) = request.cursor {
+                find = find.filter(envelopes::Column::EnvelopeId.gt(cursor));
+            }
+
+            let ids: Vec<ObjectId> = find
+                .order_by_asc(envelopes::Column::EnvelopeId)
+                .limit(MAX_BULK_SYNC_IDS as u64 + 1)
+                .into_tuple()
+                .all(&state.db)
+                .await?;
+            paginate(ids, MAX_BULK_SYNC_IDS as u64, |id| id.to_string())
+        }
+        _ => return Err(ApiError::InvalidParameter("Specify exactly one of ids or owner".to_string())),
+    };
+
+    let results = sui_indexer::sync_envelopes(&state.db, &network, package_id, rpc, &page.items).await;
+    let page = Paginated { items: results, next_cursor: page.next_cursor }
+        .map(|(id, result)| BulkSyncResult {
+            id,
+            outcome: match result {
+                Ok(sync) => SyncOutcome::Synced { claims_added: sync.claims_added },
+                Err(e) => SyncOutcome::Failed { error: format!("{:#}", e) },
+            },
+        });
+
+    Ok(Json(page))
 }
 
 pub async fn sync_claim(
     State(state): State<AppState>,
     Path(tx_digest): Path<String>,
     Query(query): Query<GeneralQuery>,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+) -> Result<Json<serde_json::Value>, ApiError> {
     let network = query.network.unwrap_or_else(|| "testnet".to_string());
     
-    // Find RPC URL
-    let network_conf = state.config.networks.iter()
-        .find(|n| n.name == network)
-        .ok_or((StatusCode::BAD_REQUEST, format!("Network {} not configured", network)))?;
+    // Find the RPC client
+    let rpc = state.rpc.get(&network)
+        .ok_or_else(|| ApiError::NetworkNotConfigured(network.clone()))?;
+    let package_id = &state.config.network(&network)
+        .ok_or_else(|| ApiError::NetworkNotConfigured(network.clone()))?
+        .package_id;
 
-    sui_indexer::sync_claim_by_tx(&state.db, &network, &network_conf.rpc_url, &tx_digest)
+    sui_indexer::sync_claim_by_tx(&state.db, &network, package_id, rpc, &tx_digest)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(ApiError::sync_failed)?;
 
     Ok(Json(json!({ "status": "success", "message": "Claim synced" })))
 }
+
+fn page_params(query: &GeneralQuery) -> Result<(u64, Order), ApiError> {
+    let limit = match query.limit {
+        Some(0) => return Err(ApiError::InvalidParameter("limit must be at least 1".to_string())),
+        Some(limit) => limit.min(MAX_PAGE_SIZE),
+        None => DEFAULT_PAGE_SIZE,
+    };
+
+    let order = match query.sort.as_deref() {
+        None | Some("desc") => Order::Desc,
+        Some("asc") => Order::Asc,
+        Some(other) => return Err(ApiError::InvalidParameter(format!("Invalid sort: {}", other))),
+    };
+
+    Ok((limit, order))
+}
+
+fn envelope_filters(query: &GeneralQuery) -> Result<Condition, ApiError> {
+    let bad_request = |message: &str| ApiError::InvalidParameter(message.to_string());
+
+    if matches!((query.min_amount, query.max_amount), (Some(min), Some(max)) if min > max) {
+        return Err(bad_request("min_amount must not exceed max_amount"));
+    }
+    if query.min_amount.is_some_and(|min| min.is_sign_negative()) {
+        return Err(bad_request("min_amount must not be negative"));
+    }
+    if query.min_remaining.is_some_and(|min| min < 0) {
+        return Err(bad_request("min_remaining must not be negative"));
+    }
+    if matches!((query.created_after, query.created_before), (Some(after), Some(before)) if after > before) {
+        return Err(bad_request("created_after must not be later than created_before"));
+    }
+
+    Ok(Condition::all()
+        .add_option(query.coin_type.as_ref().map(|c| envelopes::Column::CoinType.eq(c.to_string())))
+        .add_option(query.mode.map(|m| envelopes::Column::Mode.eq(m.as_i16())))
+        .add_option(query.requires_verification.map(|v| envelopes::Column::RequiresVerification.eq(v)))
+        .add_option(query.min_amount.map(|a| envelopes::Column::TotalAmount.gte(a)))
+        .add_option(query.max_amount.map(|a| envelopes::Column::TotalAmount.lte(a)))
+        .add_option(query.min_remaining.map(|n| envelopes::Column::RemainingCount.gte(n)))
+        .add_option(query.created_after.map(|t| envelopes::Column::CreatedAt.gte(t.naive_utc())))
+        .add_option(query.created_before.map(|t| envelopes::Column::CreatedAt.lte(t.naive_utc()))))
+}
+
+/// Cursors are `<timestamp micros>_<tiebreaker>` for the last row of the previous page.
+fn encode_cursor(at: NaiveDateTime, key: &impl Display) -> String {
+    format!("{}_{}", at.and_utc().timestamp_micros(), key)
+}
+
+fn decode_cursor<K: FromStr>(cursor: &str) -> Result<(NaiveDateTime, K), ApiError> {
+    let invalid = || ApiError::InvalidParameter(format!("Invalid cursor: {}", cursor));
+
+    let (micros, key) = cursor.split_once('_').ok_or_else(invalid)?;
+    let at = micros.parse::<i64>().ok()
+        .and_then(DateTime::from_timestamp_micros)
+        .ok_or_else(invalid)?
+        .naive_utc();
+    let key = key.parse::<K>().map_err(|_| invalid())?;
+
+    Ok((at, key))
+}
+
+/// Rows strictly after `(at, key)` in the given order.
+fn after_cursor<C: ColumnTrait>(
+    time_column: C,
+    key_column: C,
+    at: NaiveDateTime,
+    key: impl Into<Value>,
+    order: &Order,
+) -> Condition {
+    let (time_past, key_past) = match order {
+        Order::Asc => (time_column.gt(at), key_column.gt(key)),
+        _ => (time_column.lt(at), key_column.lt(key)),
+    };
+
+    Condition::any()
+        .add(time_past)
+        .add(Condition::all().add(time_column.eq(at)).add(key_past))
+}
+
+/// Trims the extra row fetched past `limit` and turns it into `next_cursor`.
+fn paginate<T>(mut rows: Vec<T>, limit: u64, cursor_of: impl Fn(&T) -> String) -> Paginated<T> {
+    let has_more = rows.len() as u64 > limit;
+    rows.truncate(limit as usize);
+
+    let next_cursor = if has_more { rows.last().map(cursor_of) } else { None };
+    Paginated { items: rows, next_cursor }
+}
+
+/// Coin metadata for the envelopes of one response, loaded with a single query.
+struct CoinRegistry(HashMap<(String, String), coins::Model>);
+
+impl CoinRegistry {
+    async fn load<'a>(
+        db: &DatabaseConnection,
+        envelopes: impl Iterator<Item = &'a envelopes::Model>,
+    ) -> Result<Self, DbErr> {
+        let mut condition = Condition::any();
+        let mut wanted = 0;
+        for envelope in envelopes {
+            condition = condition.add(
+                Condition::all()
+                    .add(coins::Column::Network.eq(envelope.network.as_str()))
+                    .add(coins::Column::CoinType.eq(envelope.coin_type.as_str())),
+            );
+            wanted += 1;
+        }
+        if wanted == 0 {
+            return Ok(Self(HashMap::new()));
+        }
+
+        let coins = coins::Entity::find().filter(condition).all(db).await?;
+        Ok(Self(coins.into_iter().map(|c| ((c.network.clone(), c.coin_type.clone()), c)).collect()))
+    }
+
+    fn get(&self, network: &str, coin_type: &str) -> Option<&coins::Model> {
+        self.0.get(&(network.to_string(), coin_type.to_string()))
+    }
+
+    fn envelope(&self, envelope: envelopes::Model) -> EnvelopeView {
+        let coin = self.get(&envelope.network, &envelope.coin_type);
+        EnvelopeView {
+            formatted_amount: coin.and_then(|c| format_amount(envelope.total_amount, c.decimals)),
+            coin: coin.map(|c| CoinInfo {
+                name: c.name.clone(),
+                symbol: c.symbol.clone(),
+                decimals: c.decimals,
+                icon_url: c.icon_url.clone(),
+            }),
+            envelope,
+        }
+    }
+
+    fn claim(&self, claim: claims::Model, coin_type: &str) -> ClaimView {
+        let coin = self.get(&claim.network, coin_type);
+        ClaimView {
+            formatted_amount: coin.and_then(|c| format_amount(claim.amount, c.decimals)),
+            claim,
+        }
+    }
+}
+
+/// Base units to whole coins without trailing zeros; `None` for decimals `Decimal` can't represent.
+fn format_amount(amount: Decimal, decimals: i16) -> Option<String> {
+    let scale = u32::try_from(decimals).ok()?;
+    let mantissa = amount.trunc().mantissa();
+    let value = Decimal::try_from_i128_with_scale(mantissa, scale).ok()?;
+    Some(value.normalize().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENVELOPE: &str = "0x1";
+
+    fn at(secs: i64) -> NaiveDateTime {
+        DateTime::from_timestamp(secs, 0).unwrap().naive_utc()
+    }
+
+    fn envelope(mode: EnvelopeMode, total_count: i64) -> envelopes::Model {
+        envelopes::Model {
+            envelope_id: ENVELOPE.parse().unwrap(),
+            network: "testnet".to_string(),
+            owner: "0x2".parse().unwrap(),
+            coin_type: "0x2::sui::SUI".to_string(),
+            total_amount: Decimal::from(100),
+            total_count,
+            mode: mode.as_i16(),
+            remaining_count: 0,
+            is_active: true,
+            requires_verification: false,
+            created_at: at(600),
+            tx_digest: "create".to_string(),
+        }
+    }
+
+    /// Claims of `amounts`, `(seconds after creation, amount)`, in claim order.
+    fn claims(amounts: &[(i64, i64)]) -> Vec<claims::Model> {
+        amounts.iter().enumerate().map(|(i, &(after, amount))| claims::Model {
+            claim_id: i as i64 + 1,
+            envelope_id: ENVELOPE.parse().unwrap(),
+            network: "testnet".to_string(),
+            claimer: format!("0x{}", i + 10).parse().unwrap(),
+            amount: Decimal::from(amount),
+            claimed_at: at(600 + after),
+            tx_digest: format!("claim{}", i),
+            event_seq: "0".to_string(),
+        }).collect()
+    }
+
+    #[test]
+    fn median_of_odd_and_even_claim_counts() {
+        let odd = envelope_stats(envelope(EnvelopeMode::Random, 5), &claims(&[(1, 30), (2, 10), (3, 20)]), Decimal::ZERO);
+        assert_eq!(odd.median_claim, Some(Decimal::from(20)));
+        assert_eq!(odd.min_claim, Some(Decimal::from(10)));
+        assert_eq!(odd.max_claim, Some(Decimal::from(30)));
+
+        let even = envelope_stats(envelope(EnvelopeMode::Random, 5), &claims(&[(1, 40), (2, 10), (3, 20), (4, 25)]), Decimal::ZERO);
+        assert_eq!(even.median_claim, Some(Decimal::new(225, 1)));
+
+        let none = envelope_stats(envelope(EnvelopeMode::Random, 5), &[], Decimal::ZERO);
+        assert_eq!(none.median_claim, None);
+        assert_eq!(none.mean_claim, None);
+    }
+
+    #[test]
+    fn luckiest_claim_is_the_earliest_of_equal_amounts() {
+        let claims = claims(&[(1, 10), (2, 50), (3, 50), (4, 20)]);
+
+        let random = envelope_stats(envelope(EnvelopeMode::Random, 4), &claims, Decimal::ZERO);
+        let luckiest = random.luckiest.unwrap();
+        assert_eq!(luckiest.amount, Decimal::from(50));
+        assert_eq!(luckiest.tx_digest, "claim1");
+
+        let equal = envelope_stats(envelope(EnvelopeMode::Equal, 4), &claims, Decimal::ZERO);
+        assert!(equal.luckiest.is_none());
+    }
+
+    #[test]
+    fn time_to_empty_needs_every_share_claimed() {
+        let claims = claims(&[(5, 10), (90, 20)]);
+
+        let empty = envelope_stats(envelope(EnvelopeMode::Random, 2), &claims, Decimal::ZERO);
+        assert_eq!(empty.time_to_first_claim, Some(5));
+        assert_eq!(empty.time_to_empty, Some(90));
+
+        let partial = envelope_stats(envelope(EnvelopeMode::Random, 3), &claims, Decimal::ZERO);
+        assert_eq!(partial.time_to_first_claim, Some(5));
+        assert_eq!(partial.time_to_empty, None);
+    }
+
+    #[test]
+    fn buckets_claims_by_minute() {
+        // Created at 600s, so claims 0..60s after fall into the same minute.
+        let stats = envelope_stats(envelope(EnvelopeMode::Random, 5), &claims(&[(0, 1), (59, 1), (60, 1), (185, 1)]), Decimal::ZERO);
+        let buckets: Vec<_> = stats.claims_per_minute.iter().map(|b| (b.minute, b.count)).collect();
+        assert_eq!(buckets, vec![(at(600), 2), (at(660), 1), (at(780), 1)]);
+    }
+
+    #[test]
+    fn remaining_amount_subtracts_claims_and_refunds() {
+        let stats = envelope_stats(envelope(EnvelopeMode::Random, 5), &claims(&[(1, 30), (2, 10)]), Decimal::from(50));
+        assert_eq!(stats.claimed_total, Decimal::from(40));
+        assert_eq!(stats.remaining_amount, Decimal::from(10));
+        assert_eq!(stats.mean_claim, Some(Decimal::from(20)));
+
+        let over = envelope_stats(envelope(EnvelopeMode::Random, 5), &claims(&[(1, 80)]), Decimal::from(50));
+        assert_eq!(over.remaining_amount, Decimal::ZERO);
+    }
+
+    #[test]
+    fn formats_amounts_in_whole_coins() {
+        assert_eq!(format_amount(Decimal::from(1_500_000_000u64), 9).as_deref(), Some("1.5"));
+        assert_eq!(format_amount(Decimal::from(1_000_000), 6).as_deref(), Some("1"));
+        assert_eq!(format_amount(Decimal::from(1), 6).as_deref(), Some("0.000001"));
+        assert_eq!(format_amount(Decimal::from(42), 0).as_deref(), Some("42"));
+        assert_eq!(format_amount(Decimal::ZERO, 9).as_deref(), Some("0"));
+    }
+
+    #[test]
+    fn formats_nothing_for_unrepresentable_decimals() {
+        assert_eq!(format_amount(Decimal::from(1), -1), None);
+        assert_eq!(format_amount(Decimal::from(1), 29), None);
+    }
+
+    #[test]
+    fn cursors_round_trip() {
+        let created_at = DateTime::from_timestamp_micros(1_700_000_000_123_456).unwrap().naive_utc();
+        let id: ObjectId = ENVELOPE.parse().unwrap();
+
+        let cursor = encode_cursor(created_at, &id);
+        let (at, key) = decode_cursor::<ObjectId>(&cursor).unwrap();
+        assert_eq!((at, key), (created_at, id));
+
+        let (at, key) = decode_cursor::<i64>(&encode_cursor(created_at, &7)).unwrap();
+        assert_eq!((at, key), (created_at, 7));
+    }
+
+    #[test]
+    fn rejects_malformed_cursors() {
+        for cursor in ["", "123", "abc_1", "123_", "123_abc", "99999999999999999999_1"] {
+            assert!(
+                matches!(decode_cursor::<i64>(cursor), Err(ApiError::InvalidParameter(_))),
+                "{cursor}"
+            );
+        }
+    }
+
+    #[test]
+    fn after_cursor_breaks_timestamp_ties_by_key() {
+        let sql = |order| claims::Entity::find()
+            .filter(after_cursor(claims::Column::ClaimedAt, claims::Column::ClaimId, at(600), 7i64, &order))
+            .build(DbBackend::MySql)
+            .to_string();
+
+        assert!(sql(Order::Desc).ends_with(
+            "WHERE `claims`.`claimed_at` < '1970-01-01 00:10:00.000000' \
+             OR (`claims`.`claimed_at` = '1970-01-01 00:10:00.000000' AND `claims`.`claim_id` < 7)"
+        ), "{}", sql(Order::Desc));
+        assert!(sql(Order::Asc).ends_with(
+            "WHERE `claims`.`claimed_at` > '1970-01-01 00:10:00.000000' \
+             OR (`claims`.`claimed_at` = '1970-01-01 00:10:00.000000' AND `claims`.`claim_id` > 7)"
+        ), "{}", sql(Order::Asc));
+    }
+}
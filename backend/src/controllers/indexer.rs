@@ -0,0 +1,49 @@
+use axum::extract::State;
+use sea_orm::*;
+use serde::Serialize;
+use crate::AppState;
+use crate::error::ApiError;
+use crate::extract::Json;
+use crate::models::indexer_state;
+use crate::services::sui_rpc::EndpointHealth;
+
+#[derive(Serialize)]
+pub struct IndexerStatus {
+    pub network: String,
+    /// `None` until the first event on this network has been processed.
+    pub checkpoint: Option<indexer_state::Model>,
+    /// Seconds between now and the timestamp of the last processed event.
+    pub lag_seconds: Option<i64>,
+    pub rpc_endpoints: Vec<EndpointHealth>,
+    pub ws_endpoints: Vec<EndpointHealth>,
+}
+
+pub async fn status(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<IndexerStatus>>, ApiError> {
+    let checkpoints = indexer_state::Entity::find()
+        .all(&state.db)
+        .await?;
+
+    let now = chrono::Utc::now().naive_utc();
+
+    let result = state.config.networks.iter()
+        .map(|network| {
+            let checkpoint = checkpoints.iter().find(|c| c.network == network.name).cloned();
+            let lag_seconds = checkpoint.as_ref().map(|c| (now - c.event_timestamp).num_seconds());
+            IndexerStatus {
+                network: network.name.clone(),
+                checkpoint,
+                lag_seconds,
+                rpc_endpoints: state.rpc.get(&network.name)
+                    .map(|rpc| rpc.endpoint_health())
+                    .unwrap_or_default(),
+                ws_endpoints: state.rpc.get(&network.name)
+                    .map(|rpc| rpc.ws_endpoint_health())
+                    .unwrap_or_default(),
+            }
+        })
+        .collect();
+
+    Ok(Json(result))
+}
@@ -0,0 +1,214 @@
+use std::sync::Arc;
+use std::time::Duration;
+use axum::extract::State;
+use chrono::{NaiveDateTime, Utc};
+use rust_decimal::Decimal;
+use sea_orm::*;
+use sea_orm::sea_query::Expr;
+use serde::{Deserialize, Serialize};
+use crate::AppState;
+use crate::address::SuiAddress;
+use crate::cache::TtlCache;
+use crate::config::Config;
+use crate::controllers::envelopes::EnvelopeMode;
+use crate::error::ApiError;
+use crate::extract::{Json, Query};
+use crate::models::{envelopes, claims};
+use crate::type_tag::TypeTag;
+
+const CACHE_TTL: Duration = Duration::from_secs(60);
+/// Distinct queries cached per board type; every parseable coin type makes a new key.
+const CACHE_CAPACITY: usize = 1_000;
+const DEFAULT_LIMIT: u64 = 10;
+const MAX_LIMIT: u64 = 100;
+
+#[derive(Deserialize, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Window {
+    #[serde(rename = "24h")]
+    Day,
+    #[serde(rename = "7d")]
+    Week,
+    #[serde(rename = "30d")]
+    Month,
+    #[default]
+    #[serde(rename = "all")]
+    All,
+}
+
+impl Window {
+    fn since(self) -> Option<NaiveDateTime> {
+        let days = match self {
+            Self::Day => 1,
+            Self::Week => 7,
+            Self::Month => 30,
+            Self::All => return None,
+        };
+        Some((Utc::now() - chrono::Duration::days(days)).naive_utc())
+    }
+}
+
+/// Amounts of different coins can't be compared, so every board is for one coin type.
+#[derive(Deserialize, Clone, PartialEq, Eq, Hash)]
+pub struct LeaderboardQuery {
+    pub coin_type: TypeTag,
+    pub network: Option<String>,
+    #[serde(default)]
+    pub window: Window,
+    pub limit: Option<u64>,
+}
+
+impl LeaderboardQuery {
+    fn limit(&self) -> u64 {
+        self.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT)
+    }
+
+    /// An unknown network would only ever rank nothing, but still cost a query and a cache entry.
+    fn check_network(&self, config: &Config) -> Result<(), ApiError> {
+        match &self.network {
+            Some(network) if config.network(network).is_none() => Err(ApiError::NetworkNotConfigured(network.clone())),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Serialize, Clone, FromQueryResult)]
+pub struct RankEntry {
+    pub address: SuiAddress,
+    /// Sum of amounts in the coin's smallest unit.
+    pub total: Decimal,
+    /// Envelopes created or claims made.
+    pub count: i64,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Board {
+    Senders,
+    Claimers,
+}
+
+/// Boards are aggregates over whole tables, so identical requests within [`CACHE_TTL`]
+/// share one result.
+pub struct LeaderboardCache {
+    ranks: TtlCache<(Board, LeaderboardQuery), Arc<Vec<RankEntry>>>,
+    luckiest: TtlCache<LeaderboardQuery, Arc<Vec<claims::Model>>>,
+}
+
+impl Default for LeaderboardCache {
+    fn default() -> Self {
+        Self {
+            ranks: TtlCache::new(CACHE_TTL, CACHE_CAPACITY),
+            luckiest: TtlCache::new(CACHE_TTL, CACHE_CAPACITY),
+        }
+    }
+}
+
+/// Addresses ranked by the total they put into envelopes.
+pub async fn top_senders(
+    State(state): State<AppState>,
+    Query(query): Query<LeaderboardQuery>,
+) -> Result<Json<Arc<Vec<RankEntry>>>, ApiError> {
+    query.check_network(&state.config)?;
+    let key = (Board::Senders, query.clone());
+    if let Some(cached) = state.leaderboard.ranks.get(&key) {
+        return Ok(Json(cached));
+    }
+
+    let mut find = envelopes::Entity::find()
+        .select_only()
+        .column_as(envelopes::Column::Owner, "address")
+        .column_as(Expr::col(envelopes::Column::TotalAmount).sum(), "total")
+        .column_as(Expr::col(envelopes::Column::EnvelopeId).count(), "count")
+        .filter(envelopes::Column::CoinType.eq(query.coin_type.to_string()));
+
+    if let Some(ref network) = query.network {
+        find = find.filter(envelopes::Column::Network.eq(network));
+    }
+    if let Some(since) = query.window.since() {
+        find = find.filter(envelopes::Column::CreatedAt.gte(since));
+    }
+
+    let ranks = Arc::new(find
+        .group_by(envelopes::Column::Owner)
+        .order_by_desc(Expr::col(envelopes::Column::TotalAmount).sum())
+        .limit(query.limit())
+        .into_model::<RankEntry>()
+        .all(&state.db)
+        .await?);
+
+    state.leaderboard.ranks.insert(key, ranks.clone());
+    Ok(Json(ranks))
+}
+
+/// Addresses ranked by the total they claimed.
+pub async fn top_claimers(
+    State(state): State<AppState>,
+    Query(query): Query<LeaderboardQuery>,
+) -> Result<Json<Arc<Vec<RankEntry>>>, ApiError> {
+    query.check_network(&state.config)?;
+    let key = (Board::Claimers, query.clone());
+    if let Some(cached) = state.leaderboard.ranks.get(&key) {
+        return Ok(Json(cached));
+    }
+
+    let mut find = claimed_from(&query)
+        .select_only()
+        .column_as(claims::Column::Claimer, "address")
+        .column_as(Expr::col((claims::Entity, claims::Column::Amount)).sum(), "total")
+        .column_as(Expr::col((claims::Entity, claims::Column::ClaimId)).count(), "count");
+
+    if let Some(since) = query.window.since() {
+        find = find.filter(claims::Column::ClaimedAt.gte(since));
+    }
+
+    let ranks = Arc::new(find
+        .group_by(claims::Column::Claimer)
+        .order_by_desc(Expr::col((claims::Entity, claims::Column::Amount)).sum())
+        .limit(query.limit())
+        .into_model::<RankEntry>()
+        .all(&state.db)
+        .await?);
+
+    state.leaderboard.ranks.insert(key, ranks.clone());
+    Ok(Json(ranks))
+}
+
+/// The largest single claims from random-mode envelopes.
+pub async fn luckiest_claims(
+    State(state): State<AppState>,
+    Query(query): Query<LeaderboardQuery>,
+) -> Result<Json<Arc<Vec<claims::Model>>>, ApiError> {
+    query.check_network(&state.config)?;
+    if let Some(cached) = state.leaderboard.luckiest.get(&query) {
+        return Ok(Json(cached));
+    }
+
+    // Equal splits have no luck in them.
+    let mut find = claimed_from(&query)
+        .filter(envelopes::Column::Mode.eq(EnvelopeMode::Random.as_i16()));
+
+    if let Some(since) = query.window.since() {
+        find = find.filter(claims::Column::ClaimedAt.gte(since));
+    }
+
+    let luckiest = Arc::new(find
+        .order_by_desc(claims::Column::Amount)
+        .order_by_asc(claims::Column::ClaimedAt)
+        .limit(query.limit())
+        .all(&state.db)
+        .await?);
+
+    state.leaderboard.luckiest.insert(query, luckiest.clone());
+    Ok(Json(luckiest))
+}
+
+/// Claims joined to their envelope, restricted to the query's coin type and network.
+fn claimed_from(query: &LeaderboardQuery) -> Select<claims::Entity> {
+    let mut find = claims::Entity::find()
+        .join(JoinType::InnerJoin, claims::Relation::Envelopes.def())
+        .filter(envelopes::Column::CoinType.eq(query.coin_type.to_string()));
+
+    if let Some(ref network) = query.network {
+        find = find.filter(claims::Column::Network.eq(network));
+    }
+    find
+}
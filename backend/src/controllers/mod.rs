@@ -1,2 +1,7 @@
 pub mod envelopes;
 pub mod verification;
+pub mod indexer;
+pub mod admin;
+pub mod users;
+pub mod leaderboard;
+pub mod stats;
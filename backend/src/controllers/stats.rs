@@ -0,0 +1,243 @@
+use std::collections::BTreeMap;
+use axum::extract::State;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use rust_decimal::Decimal;
+use sea_orm::*;
+use sea_orm::sea_query::{Alias, Expr, SimpleExpr};
+use serde::{Deserialize, Serialize};
+use crate::AppState;
+use crate::error::ApiError;
+use crate::extract::{Json, Query};
+use crate::models::{envelopes, claims};
+use crate::type_tag::TypeTag;
+
+/// Upper bounds on a timeseries range, so one request can't aggregate years by the hour.
+const MAX_DAILY_SPAN_DAYS: i64 = 366;
+const MAX_HOURLY_SPAN_DAYS: i64 = 31;
+
+/// Activity for one network and coin type. Volumes are in the coin's smallest unit.
+#[derive(Serialize, Default)]
+pub struct Metrics {
+    pub envelopes_created: i64,
+    pub claims: i64,
+    pub volume_sent: Decimal,
+    pub volume_claimed: Decimal,
+    pub unique_senders: i64,
+    pub unique_claimers: i64,
+}
+
+#[derive(Serialize)]
+pub struct OverviewRow {
+    pub network: String,
+    pub coin_type: String,
+    #[serde(flatten)]
+    pub metrics: Metrics,
+}
+
+#[derive(Serialize)]
+pub struct TimeseriesPoint {
+    /// Start of the hour or day (UTC).
+    pub bucket: NaiveDateTime,
+    pub network: String,
+    pub coin_type: String,
+    #[serde(flatten)]
+    pub metrics: Metrics,
+}
+
+#[derive(Deserialize)]
+pub struct OverviewQuery {
+    pub network: Option<String>,
+    pub coin_type: Option<TypeTag>,
+}
+
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Interval {
+    Hour,
+    #[default]
+    Day,
+}
+
+impl Interval {
+    /// MySQL `DATE_FORMAT` pattern that truncates a timestamp to the start of its bucket.
+    fn date_format(self) -> &'static str {
+        match self {
+            Self::Hour => "%Y-%m-%d %H:00:00",
+            Self::Day => "%Y-%m-%d 00:00:00",
+        }
+    }
+
+    fn max_span(self) -> chrono::Duration {
+        match self {
+            Self::Hour => chrono::Duration::days(MAX_HOURLY_SPAN_DAYS),
+            Self::Day => chrono::Duration::days(MAX_DAILY_SPAN_DAYS),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct TimeseriesQuery {
+    pub network: Option<String>,
+    pub coin_type: Option<TypeTag>,
+    #[serde(default)]
+    pub interval: Interval,
+    /// RFC 3339; defaults to 30 days (daily) or 2 days (hourly) before `to`.
+    pub from: Option<DateTime<Utc>>,
+    /// RFC 3339; defaults to now.
+    pub to: Option<DateTime<Utc>>,
+}
+
+#[derive(FromQueryResult)]
+struct AggregateRow {
+    bucket: Option<String>,
+    network: String,
+    coin_type: String,
+    count: i64,
+    volume: Decimal,
+    uniques: i64,
+}
+
+struct Filter<'a> {
+    network: Option<&'a str>,
+    coin_type: Option<&'a TypeTag>,
+    range: Option<(NaiveDateTime, NaiveDateTime)>,
+    interval: Option<Interval>,
+}
+
+type MetricsKey = (Option<String>, String, String);
+
+pub async fn overview(
+    State(state): State<AppState>,
+    Query(query): Query<OverviewQuery>,
+) -> Result<Json<Vec<OverviewRow>>, ApiError> {
+    let filter = Filter {
+        network: query.network.as_deref(),
+        coin_type: query.coin_type.as_ref(),
+        range: None,
+        interval: None,
+    };
+
+    let rows = aggregate(&state.db, &filter).await?
+        .into_iter()
+        .map(|((_, network, coin_type), metrics)| OverviewRow { network, coin_type, metrics })
+        .collect();
+
+    Ok(Json(rows))
+}
+
+pub async fn timeseries(
+    State(state): State<AppState>,
+    Query(query): Query<TimeseriesQuery>,
+) -> Result<Json<Vec<TimeseriesPoint>>, ApiError> {
+    let to = query.to.unwrap_or_else(Utc::now);
+    let from = query.from.unwrap_or_else(|| match query.interval {
+        Interval::Hour => to - chrono::Duration::days(2),
+        Interval::Day => to - chrono::Duration::days(30),
+    });
+
+    if from > to {
+        return Err(ApiError::InvalidParameter("from must not be later than to".to_string()));
+    }
+    if to - from > query.interval.max_span() {
+        return Err(ApiError::InvalidParameter(format!(
+            "Range too long for this interval; at most {} days",
+            query.interval.max_span().num_days()
+        )));
+    }
+
+    let filter = Filter {
+        network: query.network.as_deref(),
+        coin_type: query.coin_type.as_ref(),
+        range: Some((from.naive_utc(), to.naive_utc())),
+        interval: Some(query.interval),
+    };
+
+    let mut points = Vec::new();
+    for ((bucket, network, coin_type), metrics) in aggregate(&state.db, &filter).await? {
+        let Some(bucket) = bucket.as_deref()
+            .and_then(|b| NaiveDateTime::parse_from_str(b, "%Y-%m-%d %H:%M:%S").ok())
+        else {
+            continue;
+        };
+        points.push(TimeseriesPoint { bucket, network, coin_type, metrics });
+    }
+
+    Ok(Json(points))
+}
+
+/// Runs the envelope and claim aggregates and merges them per (bucket, network, coin type).
+async fn aggregate(db: &DatabaseConnection, filter: &Filter<'_>) -> Result<BTreeMap<MetricsKey, Metrics>, DbErr> {
+    let mut sent = envelopes::Entity::find()
+        .select_only()
+        .column_as(bucket_expr(filter.interval, "envelopes", "created_at"), "bucket")
+        .column(envelopes::Column::Network)
+        .column(envelopes::Column::CoinType)
+        .column_as(Expr::col(envelopes::Column::EnvelopeId).count(), "count")
+        .column_as(Expr::col(envelopes::Column::TotalAmount).sum(), "volume")
+        .column_as(Expr::col(envelopes::Column::Owner).count_distinct(), "uniques")
+        .group_by(envelopes::Column::Network)
+        .group_by(envelopes::Column::CoinType);
+
+    let mut claimed = claims_per_coin()
+        .column_as(bucket_expr(filter.interval, "claims", "claimed_at"), "bucket")
+        .column_as(Expr::col((claims::Entity, claims::Column::ClaimId)).count(), "count")
+        .column_as(Expr::col((claims::Entity, claims::Column::Amount)).sum(), "volume")
+        .column_as(Expr::col((claims::Entity, claims::Column::Claimer)).count_distinct(), "uniques");
+
+    if filter.interval.is_some() {
+        sent = sent.group_by(Expr::col(Alias::new("bucket")));
+        claimed = claimed.group_by(Expr::col(Alias::new("bucket")));
+    }
+    if let Some(network) = filter.network {
+        sent = sent.filter(envelopes::Column::Network.eq(network));
+        claimed = claimed.filter(claims::Column::Network.eq(network));
+    }
+    if let Some(coin_type) = filter.coin_type.map(|t| t.to_string()) {
+        sent = sent.filter(envelopes::Column::CoinType.eq(coin_type.as_str()));
+        claimed = claimed.filter(envelopes::Column::CoinType.eq(coin_type));
+    }
+    if let Some((from, to)) = filter.range {
+        sent = sent.filter(envelopes::Column::CreatedAt.between(from, to));
+        claimed = claimed.filter(claims::Column::ClaimedAt.between(from, to));
+    }
+
+    let mut merged: BTreeMap<MetricsKey, Metrics> = BTreeMap::new();
+
+    for row in sent.into_model::<AggregateRow>().all(db).await? {
+        let metrics = merged.entry((row.bucket, row.network, row.coin_type)).or_default();
+        metrics.envelopes_created = row.count;
+        metrics.volume_sent = row.volume;
+        metrics.unique_senders = row.uniques;
+    }
+    for row in claimed.into_model::<AggregateRow>().all(db).await? {
+        let metrics = merged.entry((row.bucket, row.network, row.coin_type)).or_default();
+        metrics.claims = row.count;
+        metrics.volume_claimed = row.volume;
+        metrics.unique_claimers = row.uniques;
+    }
+
+    Ok(merged)
+}
+
+/// Claims grouped by network and coin type, selecting only those two columns. Claims carry no
+/// coin type; it comes from the envelope they were claimed from.
+pub fn claims_per_coin() -> Select<claims::Entity> {
+    claims::Entity::find()
+        .select_only()
+        .column(claims::Column::Network)
+        .column(envelopes::Column::CoinType)
+        .join(JoinType::InnerJoin, claims::Relation::Envelopes.def())
+        .group_by(claims::Column::Network)
+        .group_by(envelopes::Column::CoinType)
+}
+
+/// `table.column` truncated to its bucket, or `NULL` when not bucketing.
+fn bucket_expr(interval: Option<Interval>, table: &str, column: &str) -> SimpleExpr {
+    match interval {
+        Some(interval) => Expr::cust(format!(
+            "DATE_FORMAT(`{}`.`{}`, '{}')",
+            table, column, interval.date_format()
+        )),
+        None => Expr::cust("NULL"),
+    }
+}
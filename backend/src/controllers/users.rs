@@ -0,0 +1,139 @@
+use std::collections::BTreeMap;
+use axum::extract::State;
+use chrono::NaiveDateTime;
+use rust_decimal::Decimal;
+use sea_orm::*;
+use sea_orm::sea_query::{Expr, Func, SimpleExpr};
+use serde::{Deserialize, Serialize};
+use crate::AppState;
+use crate::address::SuiAddress;
+use crate::controllers::stats;
+use crate::error::ApiError;
+use crate::extract::{Json, Path, Query};
+use crate::models::{envelopes, claims};
+
+#[derive(Deserialize)]
+pub struct SummaryQuery {
+    pub network: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct UserSummary {
+    pub address: SuiAddress,
+    /// Earliest envelope created or claim made; `None` for an address with no activity.
+    pub first_activity: Option<NaiveDateTime>,
+    pub last_activity: Option<NaiveDateTime>,
+    pub coins: Vec<CoinSummary>,
+}
+
+/// Totals for one (network, coin type). Amounts are in the coin's smallest unit and are
+/// never summed across coin types.
+#[derive(Serialize)]
+pub struct CoinSummary {
+    pub network: String,
+    pub coin_type: String,
+    pub total_sent: Decimal,
+    pub envelope_count: i64,
+    pub total_received: Decimal,
+    pub claim_count: i64,
+    pub average_claim: Option<Decimal>,
+}
+
+#[derive(FromQueryResult)]
+struct SentRow {
+    network: String,
+    coin_type: String,
+    total: Decimal,
+    count: i64,
+    first_at: Option<NaiveDateTime>,
+    last_at: Option<NaiveDateTime>,
+}
+
+#[derive(FromQueryResult)]
+struct ReceivedRow {
+    network: String,
+    coin_type: String,
+    total: Decimal,
+    count: i64,
+    average: Option<Decimal>,
+    first_at: Option<NaiveDateTime>,
+    last_at: Option<NaiveDateTime>,
+}
+
+pub async fn summary(
+    State(state): State<AppState>,
+    Path(address): Path<SuiAddress>,
+    Query(query): Query<SummaryQuery>,
+) -> Result<Json<UserSummary>, ApiError> {
+    let mut sent = envelopes::Entity::find()
+        .select_only()
+        .column(envelopes::Column::Network)
+        .column(envelopes::Column::CoinType)
+        .column_as(Expr::col(envelopes::Column::TotalAmount).sum(), "total")
+        .column_as(Expr::col(envelopes::Column::EnvelopeId).count(), "count")
+        .column_as(Expr::col(envelopes::Column::CreatedAt).min(), "first_at")
+        .column_as(Expr::col(envelopes::Column::CreatedAt).max(), "last_at")
+        .filter(envelopes::Column::Owner.eq(address.clone()))
+        .group_by(envelopes::Column::Network)
+        .group_by(envelopes::Column::CoinType);
+
+    let mut received = stats::claims_per_coin()
+        .column_as(Expr::col((claims::Entity, claims::Column::Amount)).sum(), "total")
+        .column_as(Expr::col((claims::Entity, claims::Column::ClaimId)).count(), "count")
+        .column_as(SimpleExpr::from(Func::avg(Expr::col((claims::Entity, claims::Column::Amount)))), "average")
+        .column_as(Expr::col((claims::Entity, claims::Column::ClaimedAt)).min(), "first_at")
+        .column_as(Expr::col((claims::Entity, claims::Column::ClaimedAt)).max(), "last_at")
+        .filter(claims::Column::Claimer.eq(address.clone()));
+
+    if let Some(network) = query.network {
+        sent = sent.filter(envelopes::Column::Network.eq(network.as_str()));
+        received = received.filter(claims::Column::Network.eq(network));
+    }
+
+    let sent = sent.into_model::<SentRow>().all(&state.db).await?;
+    let received = received.into_model::<ReceivedRow>().all(&state.db).await?;
+
+    let first_activity = sent.iter().filter_map(|r| r.first_at)
+        .chain(received.iter().filter_map(|r| r.first_at))
+        .min();
+    let last_activity = sent.iter().filter_map(|r| r.last_at)
+        .chain(received.iter().filter_map(|r| r.last_at))
+        .max();
+
+    let mut coins: BTreeMap<(String, String), CoinSummary> = BTreeMap::new();
+    for row in sent {
+        let coin = coins_entry(&mut coins, row.network, row.coin_type);
+        coin.total_sent = row.total;
+        coin.envelope_count = row.count;
+    }
+    for row in received {
+        let coin = coins_entry(&mut coins, row.network, row.coin_type);
+        coin.total_received = row.total;
+        coin.claim_count = row.count;
+        coin.average_claim = row.average.map(|a| a.round_dp(2));
+    }
+
+    Ok(Json(UserSummary {
+        address,
+        first_activity,
+        last_activity,
+        coins: coins.into_values().collect(),
+    }))
+}
+
+fn coins_entry(
+    coins: &mut BTreeMap<(String, String), CoinSummary>,
+    network: String,
+    coin_type: String,
+) -> &mut CoinSummary {
+    coins.entry((network.clone(), coin_type.clone()))
+        .or_insert_with(|| CoinSummary {
+            network,
+            coin_type,
+            total_sent: Decimal::ZERO,
+            envelope_count: 0,
+            total_received: Decimal::ZERO,
+            claim_count: 0,
+            average_claim: None,
+        })
+}
@@ -1,22 +1,24 @@
-use axum::{
-    extract::{State},
-    Json,
-    http::StatusCode,
-};
+use std::net::SocketAddr;
+use axum::extract::{ConnectInfo, State};
 use sea_orm::*;
 use serde::{Deserialize, Serialize};
 use ed25519_dalek::{SigningKey, Signer};
 use std::env;
 use hex;
 use crate::AppState;
-use crate::models::discord_users;
+use crate::address::{ObjectId, SuiAddress};
+use crate::error::ApiError;
+use crate::extract::Json;
+use crate::models::{discord_users, envelopes};
+use crate::services::sui_indexer::{self, NotAnEnvelope};
+use crate::services::sui_rpc::RpcError;
 use chrono::Utc;
 
 #[derive(Deserialize)]
 pub struct VerificationRequest {
-    pub envelope_id: String,
+    pub envelope_id: ObjectId,
     pub network: String,
-    pub claimer_address: String,
+    pub claimer_address: SuiAddress,
     pub discord_token: String,
 }
 
@@ -25,80 +27,92 @@ pub struct VerificationResponse {
     pub signature: String,
 }
 
-#[derive(Serialize)]
-pub struct JsonError {
-    pub message: String,
-}
-
-fn error_json(status: StatusCode, msg: &str) -> (StatusCode, Json<JsonError>) {
-    (status, Json(JsonError { message: msg.to_string() }))
-}
-
 pub async fn verify_discord(
     State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
     Json(payload): Json<VerificationRequest>,
-) -> Result<Json<VerificationResponse>, (StatusCode, Json<JsonError>)> {
+) -> Result<Json<VerificationResponse>, ApiError> {
+    // 1. Only sign for envelopes that exist, want verification and can still be claimed
+    let envelope = find_envelope(&state, addr, &payload.network, &payload.envelope_id).await?;
+
+    if !envelope.requires_verification {
+        return Err(ApiError::VerificationNotRequired(envelope.envelope_id));
+    }
+    if !envelope.is_active || envelope.remaining_count <= 0 {
+        return Err(ApiError::EnvelopeInactive(envelope.envelope_id));
+    }
+
     let client = reqwest::Client::new();
     
-    // 1. Get Discord User ID
+    // 2. Get Discord User ID
     let user_res = client
         .get("https://discord.com/api/users/@me")
         .header("Authorization", format!("Bearer {}", payload.discord_token))
         .send()
         .await
-        .map_err(|_| error_json(StatusCode::UNAUTHORIZED, "Failed to connect to Discord"))?;
+        .map_err(|_| ApiError::DiscordUnavailable)?;
 
     if !user_res.status().is_success() {
-        return Err(error_json(StatusCode::UNAUTHORIZED, "Invalid Discord token"));
+        return Err(ApiError::DiscordUnauthorized);
     }
 
     let user_data: serde_json::Value = user_res
         .json()
         .await
-        .map_err(|_| error_json(StatusCode::INTERNAL_SERVER_ERROR, "Failed to parse Discord user profile"))?;
+        .map_err(|_| ApiError::DiscordUnavailable)?;
 
     let discord_user_id = user_data["id"].as_str()
-        .ok_or_else(|| error_json(StatusCode::INTERNAL_SERVER_ERROR, "Discord ID not found in profile"))?
+        .ok_or(ApiError::DiscordUnavailable)?
         .to_string();
 
-    // 2. Check if this Discord account already claimed this envelope
+    // 3. Check if this Discord account already claimed this envelope
     let existing = discord_users::Entity::find()
-        .filter(discord_users::Column::EnvelopeId.eq(&payload.envelope_id))
+        .filter(discord_users::Column::EnvelopeId.eq(payload.envelope_id.clone()))
         .filter(discord_users::Column::Network.eq(&payload.network))
         .filter(discord_users::Column::DiscordUserId.eq(&discord_user_id))
         .one(&state.db)
-        .await
-        .map_err(|e| error_json(StatusCode::INTERNAL_SERVER_ERROR, &format!("DB Error: {}", e)))?;
+        .await?;
 
     if existing.is_some() {
-        return Err(error_json(StatusCode::FORBIDDEN, "This Discord account has already claimed this gift"));
+        return Err(ApiError::AlreadyVerified);
     }
 
-    // 3. Verify Guild Membership
+    // 4. Verify Guild Membership
     let discord_res = client
         .get("https://discord.com/api/users/@me/guilds")
         .header("Authorization", format!("Bearer {}", payload.discord_token))
         .send()
         .await
-        .map_err(|_| error_json(StatusCode::UNAUTHORIZED, "Failed to connect to Discord"))?;
+        .map_err(|_| ApiError::DiscordUnavailable)?;
 
     if !discord_res.status().is_success() {
-        return Err(error_json(StatusCode::UNAUTHORIZED, "Failed to fetch guilds"));
+        return Err(ApiError::DiscordUnauthorized);
     }
 
     let guilds: Vec<serde_json::Value> = discord_res
         .json()
         .await
-        .map_err(|_| error_json(StatusCode::INTERNAL_SERVER_ERROR, "Failed to parse Discord response"))?;
+        .map_err(|_| ApiError::DiscordUnavailable)?;
 
     let target_guild_id = env::var("DISCORD_GUILD_ID").unwrap_or_default();
     let is_member = guilds.iter().any(|g| g["id"].as_str() == Some(&target_guild_id));
 
     if !is_member && !target_guild_id.is_empty() {
-        return Err(error_json(StatusCode::FORBIDDEN, "You must join the Discord server first"));
+        return Err(ApiError::NotGuildMember);
     }
 
-    // 4. Record the attempt in database
+    // 5. Generate Signature
+    // Message = EnvelopeID (32 bytes) + Claimer Address (32 bytes)
+    let mut msg = payload.envelope_id.to_bytes().to_vec();
+    msg.extend_from_slice(&payload.claimer_address.to_bytes());
+
+    let secret = env::var("SIGNING_KEY_HEX").expect("SIGNING_KEY_HEX must be set");
+    let secret_bytes = hex::decode(secret).expect("Invalid SIGNING_KEY_HEX");
+    let signing_key = SigningKey::from_bytes(secret_bytes.as_slice().try_into().expect("Key must be 32 bytes"));
+    
+    let signature = signing_key.sign(&msg);
+
+    // 6. Record the verification, only once a signature is actually handed out
     let new_user_record = discord_users::ActiveModel {
         envelope_id: Set(payload.envelope_id.clone()),
         network: Set(payload.network.clone()),
@@ -108,25 +122,44 @@ pub async fn verify_discord(
         ..Default::default()
     };
 
-    new_user_record.insert(&state.db).await
-        .map_err(|e| error_json(StatusCode::INTERNAL_SERVER_ERROR, &format!("Failed to record claim: {}", e)))?;
-
-    // 5. Generate Signature
-    // Message = EnvelopeID (32 bytes) + Claimer Address (32 bytes)
-    let mut msg = hex::decode(payload.envelope_id.trim_start_matches("0x"))
-        .map_err(|_| error_json(StatusCode::BAD_REQUEST, "Invalid envelope ID"))?;
-    let mut addr_bytes = hex::decode(payload.claimer_address.trim_start_matches("0x"))
-        .map_err(|_| error_json(StatusCode::BAD_REQUEST, "Invalid claimer address"))?;
-    
-    msg.append(&mut addr_bytes);
-
-    let secret = env::var("SIGNING_KEY_HEX").expect("SIGNING_KEY_HEX must be set");
-    let secret_bytes = hex::decode(secret).expect("Invalid SIGNING_KEY_HEX");
-    let signing_key = SigningKey::from_bytes(secret_bytes.as_slice().try_into().expect("Key must be 32 bytes"));
-    
-    let signature = signing_key.sign(&msg);
+    new_user_record.insert(&state.db).await?;
     
     Ok(Json(VerificationResponse {
         signature: hex::encode(signature.to_bytes()),
     }))
 }
+
+/// The indexed envelope, inserted from chain first if the indexer hasn't seen it yet. That
+/// insert counts against the caller's sync limits, and is skipped entirely when syncs require
+/// an API key, since this route has none.
+async fn find_envelope(state: &AppState, addr: SocketAddr, network: &str, envelope_id: &ObjectId) -> Result<envelopes::Model, ApiError> {
+    let rpc = state.rpc.get(network)
+        .ok_or_else(|| ApiError::NetworkNotConfigured(network.to_string()))?;
+    let package_id = &state.config.network(network)
+        .ok_or_else(|| ApiError::NetworkNotConfigured(network.to_string()))?
+        .package_id;
+
+    let find = || envelopes::Entity::find()
+        .filter(envelopes::Column::EnvelopeId.eq(envelope_id.clone()))
+        .filter(envelopes::Column::Network.eq(network))
+        .one(&state.db);
+
+    if let Some(envelope) = find().await? {
+        return Ok(envelope);
+    }
+    if state.config.sync_api_key.is_some() {
+        return Err(ApiError::EnvelopeNotFound(envelope_id.clone()));
+    }
+
+    let _permit = state.sync_limits.acquire(addr.ip())?;
+    if let Err(e) = sui_indexer::insert_envelope_by_id(&state.db, network, package_id, rpc, envelope_id).await {
+        let missing = matches!(e.downcast_ref::<RpcError>(), Some(RpcError::ObjectNotFound(_)))
+            || e.downcast_ref::<NotAnEnvelope>().is_some();
+        if missing {
+            return Err(ApiError::EnvelopeNotFound(envelope_id.clone()));
+        }
+        return Err(ApiError::sync_failed(e));
+    }
+
+    find().await?.ok_or_else(|| ApiError::EnvelopeNotFound(envelope_id.clone()))
+}
@@ -0,0 +1,148 @@
+use axum::{
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use sea_orm::DbErr;
+use serde::Serialize;
+use serde_json::Value;
+use crate::address::ObjectId;
+
+/// Every error an API handler can return. Each variant maps to a fixed HTTP status and a
+/// machine-readable `code` that clients can branch on; the wording of `message` may change.
+#[derive(Debug, thiserror::Error)]
+pub enum ApiError {
+    #[error("Envelope {0} not found")]
+    EnvelopeNotFound(ObjectId),
+    #[error("Dead letter {0} not found")]
+    DeadLetterNotFound(i64),
+    #[error("Network {0} not configured")]
+    NetworkNotConfigured(String),
+    #[error("{0}")]
+    InvalidParameter(String),
+
+    #[error("Invalid Discord token")]
+    DiscordUnauthorized,
+    #[error("Discord is unavailable")]
+    DiscordUnavailable,
+    #[error("You must join the Discord server first")]
+    NotGuildMember,
+    #[error("This Discord account has already claimed this gift")]
+    AlreadyVerified,
+    #[error("Envelope {0} does not require Discord verification")]
+    VerificationNotRequired(ObjectId),
+    #[error("Envelope {0} has no claims left")]
+    EnvelopeInactive(ObjectId),
+
+    #[error("Admin API is disabled")]
+    AdminDisabled,
+    #[error("Invalid admin token")]
+    Unauthorized,
+    #[error("Invalid sync API key")]
+    InvalidApiKey,
+    #[error("Too many requests; retry in {retry_after_secs}s")]
+    RateLimited { retry_after_secs: u64 },
+    #[error("Too many syncs in progress; try again shortly")]
+    SyncBusy,
+
+    #[error("Failed to sync from chain")]
+    SyncFailed(#[source] anyhow::Error),
+    #[error("Replay failed")]
+    ReplayFailed(#[source] anyhow::Error),
+
+    #[error("Database error")]
+    Database(#[from] DbErr),
+}
+
+#[derive(Serialize)]
+struct ErrorBody {
+    code: &'static str,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    details: Option<Value>,
+}
+
+impl ApiError {
+    /// A failed chain sync, unless the database was what actually failed.
+    pub fn sync_failed(e: anyhow::Error) -> Self {
+        match e.downcast::<DbErr>() {
+            Ok(db) => Self::Database(db),
+            Err(e) => Self::SyncFailed(e),
+        }
+    }
+
+    /// A failed replay, unless the database was what actually failed.
+    pub fn replay_failed(e: anyhow::Error) -> Self {
+        match e.downcast::<DbErr>() {
+            Ok(db) => Self::Database(db),
+            Err(e) => Self::ReplayFailed(e),
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::EnvelopeNotFound(_) | Self::DeadLetterNotFound(_) => StatusCode::NOT_FOUND,
+            Self::NetworkNotConfigured(_) | Self::InvalidParameter(_) | Self::VerificationNotRequired(_) => StatusCode::BAD_REQUEST,
+            Self::EnvelopeInactive(_) => StatusCode::CONFLICT,
+            Self::DiscordUnauthorized | Self::Unauthorized | Self::InvalidApiKey => StatusCode::UNAUTHORIZED,
+            Self::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+            Self::SyncBusy => StatusCode::SERVICE_UNAVAILABLE,
+            Self::NotGuildMember | Self::AlreadyVerified | Self::AdminDisabled => StatusCode::FORBIDDEN,
+            Self::DiscordUnavailable | Self::SyncFailed(_) => StatusCode::BAD_GATEWAY,
+            Self::ReplayFailed(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::EnvelopeNotFound(_) => "ENVELOPE_NOT_FOUND",
+            Self::DeadLetterNotFound(_) => "DEAD_LETTER_NOT_FOUND",
+            Self::NetworkNotConfigured(_) => "NETWORK_NOT_CONFIGURED",
+            Self::InvalidParameter(_) => "INVALID_PARAMETER",
+            Self::DiscordUnauthorized => "DISCORD_UNAUTHORIZED",
+            Self::DiscordUnavailable => "DISCORD_UNAVAILABLE",
+            Self::NotGuildMember => "NOT_GUILD_MEMBER",
+            Self::AlreadyVerified => "ALREADY_VERIFIED",
+            Self::VerificationNotRequired(_) => "VERIFICATION_NOT_REQUIRED",
+            Self::EnvelopeInactive(_) => "ENVELOPE_INACTIVE",
+            Self::AdminDisabled => "ADMIN_DISABLED",
+            Self::Unauthorized => "UNAUTHORIZED",
+            Self::InvalidApiKey => "INVALID_API_KEY",
+            Self::RateLimited { .. } => "RATE_LIMITED",
+            Self::SyncBusy => "SYNC_BUSY",
+            Self::SyncFailed(_) => "SYNC_FAILED",
+            Self::ReplayFailed(_) => "REPLAY_FAILED",
+            Self::Database(_) => "DATABASE_ERROR",
+        }
+    }
+
+    /// Chain-side failures are safe to show and help the caller fix a bad ID; database errors
+    /// are only logged.
+    fn details(&self) -> Option<Value> {
+        match self {
+            Self::SyncFailed(e) | Self::ReplayFailed(e) => Some(Value::String(format!("{:#}", e))),
+            _ => None,
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        if let Self::Database(e) = &self {
+            tracing::error!("Database error: {}", e);
+        }
+
+        let body = ErrorBody {
+            code: self.code(),
+            message: self.to_string(),
+            details: self.details(),
+        };
+        let mut response = (status, Json(body)).into_response();
+        if let Self::RateLimited { retry_after_secs } = self {
+            response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(retry_after_secs));
+        }
+        response
+    }
+}
@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use crate::AppState;
+use crate::config::Config;
+use crate::controllers::admin::bearer_token;
+use crate::error::ApiError;
+
+/// Above this many tracked clients, buckets that have refilled completely are dropped; a full
+/// bucket behaves exactly like a missing one.
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+/// A token bucket per client IP: `burst` requests at once, refilled at `per_minute`.
+pub struct RateLimiter {
+    capacity: f64,
+    per_second: f64,
+    buckets: Mutex<HashMap<IpAddr, Bucket>>,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(per_minute: u32, burst: u32) -> Self {
+        Self {
+            capacity: burst as f64,
+            per_second: per_minute as f64 / 60.0,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Takes a token for `ip`, or says how long until one is available.
+    pub fn check(&self, ip: IpAddr) -> Result<(), Duration> {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+
+        if buckets.len() >= MAX_TRACKED_CLIENTS {
+            buckets.retain(|_, bucket| self.refilled(bucket, now) < self.capacity);
+        }
+
+        let bucket = buckets.entry(ip).or_insert(Bucket { tokens: self.capacity, updated: now });
+        bucket.tokens = self.refilled(bucket, now);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / self.per_second))
+        }
+    }
+
+    fn refilled(&self, bucket: &Bucket, now: Instant) -> f64 {
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        (bucket.tokens + elapsed * self.per_second).min(self.capacity)
+    }
+}
+
+/// Limits on the sync endpoints, which each cost one or more RPC calls.
+pub struct SyncLimits {
+    rate: Option<RateLimiter>,
+    in_flight: Arc<Semaphore>,
+}
+
+impl SyncLimits {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            rate: config.sync_rate_per_minute
+                .map(|per_minute| RateLimiter::new(per_minute, config.sync_rate_burst)),
+            in_flight: Arc::new(Semaphore::new(config.max_concurrent_syncs)),
+        }
+    }
+
+    /// Takes a rate-limit token for `ip` and a slot among the syncs in flight. The sync may
+    /// run for as long as the returned permit is held.
+    pub fn acquire(&self, ip: IpAddr) -> Result<OwnedSemaphorePermit, ApiError> {
+        if let Some(rate) = &self.rate {
+            rate.check(ip).map_err(|wait| ApiError::RateLimited {
+                retry_after_secs: wait.as_secs_f64().ceil() as u64,
+            })?;
+        }
+
+        self.in_flight.clone()
+            .try_acquire_owned()
+            .map_err(|_| ApiError::SyncBusy)
+    }
+}
+
+/// Guards the sync endpoints: the API key when one is configured, then the per-IP rate, then
+/// the cap on syncs in flight, whose permit is held until the response is ready.
+pub async fn guard_sync(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    if let Some(key) = &state.config.sync_api_key {
+        let admin = state.config.admin_token.as_ref().map(|t| t.expose());
+        let authorized = match bearer_token(&request) {
+            Some(provided) => provided == key.expose() || Some(provided) == admin,
+            None => false,
+        };
+        if !authorized {
+            return Err(ApiError::InvalidApiKey);
+        }
+    }
+
+    let _permit = state.sync_limits.acquire(addr.ip())?;
+
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    fn ip(n: u32) -> IpAddr {
+        IpAddr::V4(Ipv4Addr::from(n))
+    }
+
+    /// Pretends `ip` last asked `by` ago.
+    fn rewind(limiter: &RateLimiter, ip: IpAddr, by: Duration) {
+        limiter.buckets.lock().unwrap().get_mut(&ip).unwrap().updated -= by;
+    }
+
+    #[test]
+    fn allows_a_burst_then_says_how_long_to_wait() {
+        let limiter = RateLimiter::new(60, 3);
+        for _ in 0..3 {
+            assert!(limiter.check(ip(1)).is_ok());
+        }
+
+        let wait = limiter.check(ip(1)).unwrap_err();
+        assert!(wait > Duration::from_millis(900) && wait <= Duration::from_secs(1), "{wait:?}");
+        // Other clients have their own bucket.
+        assert!(limiter.check(ip(2)).is_ok());
+    }
+
+    #[test]
+    fn refills_at_the_configured_rate() {
+        let limiter = RateLimiter::new(60, 1);
+        assert!(limiter.check(ip(1)).is_ok());
+        assert!(limiter.check(ip(1)).is_err());
+
+        rewind(&limiter, ip(1), Duration::from_secs(1));
+        assert!(limiter.check(ip(1)).is_ok());
+        assert!(limiter.check(ip(1)).is_err());
+    }
+
+    #[test]
+    fn refills_no_further_than_the_burst() {
+        let limiter = RateLimiter::new(60, 2);
+        assert!(limiter.check(ip(1)).is_ok());
+        assert!(limiter.check(ip(1)).is_ok());
+
+        rewind(&limiter, ip(1), Duration::from_secs(5));
+        assert!(limiter.check(ip(1)).is_ok());
+        assert!(limiter.check(ip(1)).is_ok());
+        assert!(limiter.check(ip(1)).is_err());
+    }
+
+    #[test]
+    fn drops_refilled_buckets_at_max_tracked_clients() {
+        let limiter = RateLimiter::new(60, 2);
+        let now = Instant::now();
+        {
+            let mut buckets = limiter.buckets.lock().unwrap();
+            for n in 0..MAX_TRACKED_CLIENTS as u32 {
+                // Every other client has used a token and is still refilling.
+                let tokens = if n % 2 == 0 { 2.0 } else { 1.0 };
+                buckets.insert(ip(n), Bucket { tokens, updated: now });
+            }
+        }
+
+        assert!(limiter.check(ip(u32::MAX)).is_ok());
+
+        let buckets = limiter.buckets.lock().unwrap();
+        assert_eq!(buckets.len(), MAX_TRACKED_CLIENTS / 2 + 1);
+        assert!(buckets.contains_key(&ip(1)));
+        assert!(!buckets.contains_key(&ip(0)));
+    }
+}
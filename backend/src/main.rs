@@ -1,18 +1,30 @@
+mod address;
+mod cache;
 mod config;
 mod db;
+mod error;
+mod extract;
+mod limits;
 mod models;
+mod type_tag;
 mod services;
 mod controllers;
 
-use axum::{routing::{get, post}, Router};
+use axum::{middleware, routing::{get, post}, Router};
 use tower_http::cors::{Any, CorsLayer};
+use std::collections::HashMap;
 use std::net::SocketAddr;
+use std::sync::Arc;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 #[derive(Clone)]
 pub struct AppState {
     pub db: sea_orm::DatabaseConnection,
     pub config: config::Config,
+    /// Sui RPC client per configured network, keyed by network name.
+    pub rpc: Arc<HashMap<String, services::sui_rpc::SuiRpcClient>>,
+    pub leaderboard: Arc<controllers::leaderboard::LeaderboardCache>,
+    pub sync_limits: Arc<limits::SyncLimits>,
 }
 
 #[tokio::main]
@@ -30,18 +42,30 @@ async fn main() {
     let db = db::connect(&config).await.expect("Failed to connect to database");
     tracing::info!("Connected to database");
 
-    // Start Indexers for all configured networks if enabled
-    if config.enable_websocket {
+    let rpc: HashMap<_, _> = config.networks.iter()
+        .map(|n| (n.name.clone(), services::sui_rpc::SuiRpcClient::new(n.rpc_urls.clone(), n.ws_urls.clone())))
+        .collect();
+
+    // Start Indexers for all configured networks
+    for network_config in config.networks.clone() {
+        let client = rpc[&network_config.name].clone();
+        services::sui_indexer::start_indexer(db.clone(), network_config, client).await;
+    }
+
+    // Periodically correct envelope rows that drifted from chain state
+    if let Some(interval) = config.reconcile_interval {
         for network_config in config.networks.clone() {
-            services::sui_indexer::start_indexer(db.clone(), network_config).await;
+            let client = rpc[&network_config.name].clone();
+            services::reconciler::start_reconciler(db.clone(), network_config, client, interval).await;
         }
-    } else {
-        tracing::info!("WebSocket indexer is disabled by configuration");
     }
 
     let state = AppState {
         db,
         config: config.clone(),
+        rpc: Arc::new(rpc),
+        leaderboard: Arc::default(),
+        sync_limits: Arc::new(limits::SyncLimits::new(&config)),
     };
 
     // CORS
@@ -50,6 +74,20 @@ async fn main() {
         .allow_methods(Any)
         .allow_headers(Any);
 
+    // Operator endpoints, behind ADMIN_TOKEN
+    let admin = Router::new()
+        .route("/api/admin/dead-letters", get(controllers::admin::list_dead_letters))
+        .route("/api/admin/dead-letters/replay", post(controllers::admin::replay_all))
+        .route("/api/admin/dead-letters/:id/replay", post(controllers::admin::replay_dead_letter))
+        .route_layer(middleware::from_fn_with_state(state.clone(), controllers::admin::require_admin));
+
+    // Chain syncs cost RPC quota: optional API key, per-IP rate limit, concurrency cap
+    let sync = Router::new()
+        .route("/api/envelopes/sync", post(controllers::envelopes::sync_envelopes))
+        .route("/api/envelopes/sync/:id", post(controllers::envelopes::sync_envelope))
+        .route("/api/claims/sync/:tx_digest", post(controllers::envelopes::sync_claim))
+        .route_layer(middleware::from_fn_with_state(state.clone(), limits::guard_sync));
+
     // Web Server
     let app = Router::new()
         .route("/", get(root))
@@ -57,10 +95,18 @@ async fn main() {
         .route("/api/envelopes/active", get(controllers::envelopes::list_active))
         .route("/api/envelopes/created", get(controllers::envelopes::list_created))
         .route("/api/envelopes/claimed", get(controllers::envelopes::list_claimed))
-        .route("/api/envelopes/sync/:id", post(controllers::envelopes::sync_envelope))
-        .route("/api/claims/sync/:tx_digest", post(controllers::envelopes::sync_claim))
         .route("/api/envelopes/:id", get(controllers::envelopes::get_details))
+        .route("/api/envelopes/:id/stats", get(controllers::envelopes::get_stats))
+        .route("/api/users/:address/summary", get(controllers::users::summary))
+        .route("/api/leaderboard/senders", get(controllers::leaderboard::top_senders))
+        .route("/api/leaderboard/claimers", get(controllers::leaderboard::top_claimers))
+        .route("/api/leaderboard/luckiest", get(controllers::leaderboard::luckiest_claims))
+        .route("/api/stats/overview", get(controllers::stats::overview))
+        .route("/api/stats/timeseries", get(controllers::stats::timeseries))
         .route("/api/verify-discord", post(controllers::verification::verify_discord))
+        .route("/api/indexer/status", get(controllers::indexer::status))
+        .merge(sync)
+        .merge(admin)
         .layer(cors)
         .with_state(state);
 
@@ -69,7 +115,8 @@ async fn main() {
 
     tracing::info!("Listening on {}", addr);
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app.into_make_service()).await.unwrap();
+    // Peer addresses key the per-IP sync rate limit
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await.unwrap();
 }
 
 async fn root() -> &'static str {
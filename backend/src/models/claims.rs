@@ -1,19 +1,22 @@
 use sea_orm::entity::prelude::*;
 use rust_decimal::Decimal;
 use serde::Serialize;
+use crate::address::{ObjectId, SuiAddress};
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize)]
 #[sea_orm(table_name = "claims")]
 pub struct Model {
     #[sea_orm(primary_key)]
     pub claim_id: i64,
-    pub envelope_id: String,
+    pub envelope_id: ObjectId,
     pub network: String,
-    pub claimer: String,
+    pub claimer: SuiAddress,
     #[sea_orm(column_type = "Decimal(Some((30, 0)))")]
     pub amount: Decimal,
     pub claimed_at: DateTime,
     pub tx_digest: String,
+    /// Position of the `EnvelopeClaimed` event within its transaction; unique with (network, tx_digest).
+    pub event_seq: String,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
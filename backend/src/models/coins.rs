@@ -0,0 +1,24 @@
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+/// `suix_getCoinMetadata` for each coin type the indexer has seen, fetched once per network.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize)]
+#[sea_orm(table_name = "coins")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub network: String,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub coin_type: String,
+    pub decimals: i16,
+    pub name: String,
+    pub symbol: String,
+    #[sea_orm(column_type = "Text")]
+    pub description: String,
+    pub icon_url: Option<String>,
+    pub updated_at: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,28 @@
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+/// Events the indexer could not decode or apply, kept with their raw JSON until replayed.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize)]
+#[sea_orm(table_name = "dead_letter_events")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    pub network: String,
+    pub tx_digest: String,
+    pub event_seq: String,
+    pub event_type: String,
+    /// The full event as received from the RPC, serialized as JSON.
+    #[sea_orm(column_type = "Text")]
+    pub payload: String,
+    #[sea_orm(column_type = "Text")]
+    pub error: String,
+    /// Failed replays so far; a successful replay deletes the row.
+    pub replay_count: i32,
+    pub last_replayed_at: Option<DateTime>,
+    pub created_at: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -1,15 +1,16 @@
 use sea_orm::entity::prelude::*;
 use serde::Serialize;
+use crate::address::{ObjectId, SuiAddress};
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize)]
 #[sea_orm(table_name = "discord_users")]
 pub struct Model {
     #[sea_orm(primary_key)]
     pub id: i64,
-    pub envelope_id: String,
+    pub envelope_id: ObjectId,
     pub network: String,
     pub discord_user_id: String,
-    pub claimer_address: String,
+    pub claimer_address: SuiAddress,
     pub claimed_at: DateTime,
 }
 
@@ -1,15 +1,16 @@
 use sea_orm::entity::prelude::*;
 use rust_decimal::Decimal;
 use serde::Serialize;
+use crate::address::{ObjectId, SuiAddress};
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize)]
 #[sea_orm(table_name = "envelopes")]
 pub struct Model {
     #[sea_orm(primary_key, auto_increment = false)]
-    pub envelope_id: String,
+    pub envelope_id: ObjectId,
     #[sea_orm(primary_key, auto_increment = false)]
     pub network: String,
-    pub owner: String,
+    pub owner: SuiAddress,
     pub coin_type: String,
     #[sea_orm(column_type = "Decimal(Some((30, 0)))")]
     pub total_amount: Decimal,
@@ -26,6 +27,8 @@ pub struct Model {
 pub enum Relation {
     #[sea_orm(has_many = "super::claims::Entity")]
     Claims,
+    #[sea_orm(has_many = "super::refunds::Entity")]
+    Refunds,
 }
 
 impl Related<super::claims::Entity> for Entity {
@@ -34,4 +37,10 @@ impl Related<super::claims::Entity> for Entity {
     }
 }
 
+impl Related<super::refunds::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Refunds.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
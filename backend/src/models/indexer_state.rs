@@ -0,0 +1,19 @@
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+/// Last event processed by the indexer of each network; the resume point after a restart.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize)]
+#[sea_orm(table_name = "indexer_state")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub network: String,
+    pub tx_digest: String,
+    pub event_seq: String,
+    pub event_timestamp: DateTime,
+    pub updated_at: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
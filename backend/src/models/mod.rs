@@ -1,3 +1,8 @@
 pub mod envelopes;
 pub mod claims;
 pub mod discord_users;
+pub mod indexer_state;
+pub mod refunds;
+pub mod dead_letter_events;
+pub mod coins;
+pub mod withdrawal_state;
@@ -0,0 +1,38 @@
+use sea_orm::entity::prelude::*;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use crate::address::{ObjectId, SuiAddress};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize)]
+#[sea_orm(table_name = "refunds")]
+pub struct Model {
+    /// Digest of the `withdraw_remaining` transaction.
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub refund_id: String,
+    pub envelope_id: ObjectId,
+    pub network: String,
+    pub owner: SuiAddress,
+    #[sea_orm(column_type = "Decimal(Some((30, 0)))")]
+    pub amount: Decimal,
+    pub refunded_at: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::envelopes::Entity",
+        from = "(Column::EnvelopeId, Column::Network)",
+        to = "(super::envelopes::Column::EnvelopeId, super::envelopes::Column::Network)",
+        on_update = "NoAction",
+        on_delete = "NoAction"
+    )]
+    Envelopes,
+}
+
+impl Related<super::envelopes::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Envelopes.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,18 @@
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+/// Last `withdraw_remaining` transaction the withdrawal watcher of each network has looked at,
+/// whether or not it recorded a refund; the watcher resumes after it.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize)]
+#[sea_orm(table_name = "withdrawal_state")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub network: String,
+    pub tx_digest: String,
+    pub updated_at: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
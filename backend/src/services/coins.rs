@@ -0,0 +1,66 @@
+use sea_orm::*;
+use sea_orm::sea_query::OnConflict;
+use tracing::{info, warn};
+
+use crate::models::{coins, envelopes};
+use crate::services::sui_rpc::SuiRpcClient;
+
+/// Makes sure `coins` has metadata for `coin_type` on `network`, fetching it the first time
+/// the type is seen. Coin types without published metadata are skipped and retried next time.
+pub async fn ensure_coin<C: ConnectionTrait>(db: &C, network: &str, rpc: &SuiRpcClient, coin_type: &str) -> anyhow::Result<()> {
+    let known = coins::Entity::find_by_id((network.to_string(), coin_type.to_string()))
+        .one(db)
+        .await?;
+    if known.is_some() {
+        return Ok(());
+    }
+
+    let Some(metadata) = rpc.get_coin_metadata(coin_type).await? else {
+        warn!("[{}] No coin metadata published for {}", network, coin_type);
+        return Ok(());
+    };
+
+    let model = coins::ActiveModel {
+        network: Set(network.to_string()),
+        coin_type: Set(coin_type.to_string()),
+        decimals: Set(metadata.decimals as i16),
+        name: Set(metadata.name),
+        symbol: Set(metadata.symbol),
+        description: Set(metadata.description),
+        icon_url: Set(metadata.icon_url),
+        updated_at: Set(chrono::Utc::now().naive_utc()),
+    };
+
+    // Another event of the same coin may have won the race; its row is just as good.
+    coins::Entity::insert(model)
+        .on_conflict(
+            OnConflict::columns([coins::Column::Network, coins::Column::CoinType])
+                .do_nothing()
+                .to_owned(),
+        )
+        .exec_without_returning(db)
+        .await?;
+
+    info!("[{}] Registered coin {}", network, coin_type);
+    Ok(())
+}
+
+/// Registers the coin types of envelopes indexed before the registry existed.
+pub async fn backfill_coins(db: &DatabaseConnection, network: &str, rpc: &SuiRpcClient) -> anyhow::Result<()> {
+    let coin_types: Vec<String> = envelopes::Entity::find()
+        .select_only()
+        .column(envelopes::Column::CoinType)
+        .distinct()
+        .filter(envelopes::Column::Network.eq(network))
+        .into_tuple()
+        .all(db)
+        .await?;
+
+    for coin_type in coin_types {
+        if let Err(e) = ensure_coin(db, network, rpc, &coin_type).await {
+            warn!("[{}] Failed to register coin {}: {:?}", network, coin_type, e);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,162 @@
+use serde::{Deserialize, Deserializer};
+use thiserror::Error;
+
+use crate::address::{ObjectId, SuiAddress};
+use crate::services::sui_rpc::{u64_from_str, SuiEvent};
+use crate::type_tag::{StructTag, TypeTagError};
+
+/// Mirrors `EnvelopeCreated<phantom T>` in `sui_red_envelope.move`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct EnvelopeCreated {
+    pub id: ObjectId,
+    pub owner: SuiAddress,
+    #[serde(deserialize_with = "u64_from_str")]
+    pub amount: u64,
+    #[serde(deserialize_with = "u64_from_str")]
+    pub count: u64,
+    #[serde(deserialize_with = "u8_from_str")]
+    pub mode: u8,
+    pub requires_verification: bool,
+}
+
+/// Mirrors `EnvelopeClaimed` in `sui_red_envelope.move`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct EnvelopeClaimed {
+    pub id: ObjectId,
+    pub claimer: SuiAddress,
+    #[serde(deserialize_with = "u64_from_str")]
+    pub amount: u64,
+}
+
+#[derive(Debug, Clone)]
+pub enum EnvelopeEvent {
+    Created {
+        data: EnvelopeCreated,
+        /// `T` from the event type tag in its canonical form, when present.
+        coin_type: Option<String>,
+    },
+    Claimed(EnvelopeClaimed),
+}
+
+#[derive(Debug, Error)]
+pub enum DecodeError {
+    #[error(transparent)]
+    Type(#[from] TypeTagError),
+    #[error("Invalid {event} payload: {source}")]
+    Payload {
+        event: &'static str,
+        source: serde_json::Error,
+    },
+    #[error("Invalid {event} payload: {reason}")]
+    Invalid {
+        event: &'static str,
+        reason: &'static str,
+    },
+}
+
+/// The Move module that defines the envelope events and the `RedEnvelope` object.
+pub const MODULE: &str = "sui_red_envelope";
+
+/// Whether `tag` is declared in [`MODULE`] of `package_id` (long form). Anyone can publish a
+/// module with an `EnvelopeClaimed` of their own, so the name alone proves nothing.
+pub fn is_own_type(tag: &StructTag, package_id: &str) -> bool {
+    tag.address == package_id && tag.module == MODULE
+}
+
+/// Decodes an event of the module in `package_id`. `Ok(None)` for event types the indexer does
+/// not track, including look-alikes from other packages.
+pub fn decode(event: &SuiEvent, package_id: &str) -> Result<Option<EnvelopeEvent>, DecodeError> {
+    let tag: StructTag = event.type_.parse()?;
+    if !is_own_type(&tag, package_id) {
+        return Ok(None);
+    }
+
+    match tag.name.as_str() {
+        "EnvelopeCreated" => {
+            let data: EnvelopeCreated = parse_payload("EnvelopeCreated", event)?;
+            // The contract asserts both, so a zero here means the payload is not what we think it is.
+            if data.amount == 0 || data.count == 0 {
+                return Err(DecodeError::Invalid { event: "EnvelopeCreated", reason: "zero amount or count" });
+            }
+            let coin_type = tag.type_params.first().map(|t| t.to_string());
+            Ok(Some(EnvelopeEvent::Created { data, coin_type }))
+        }
+        "EnvelopeClaimed" => {
+            let data: EnvelopeClaimed = parse_payload("EnvelopeClaimed", event)?;
+            Ok(Some(EnvelopeEvent::Claimed(data)))
+        }
+        _ => Ok(None),
+    }
+}
+
+fn parse_payload<T: serde::de::DeserializeOwned>(name: &'static str, event: &SuiEvent) -> Result<T, DecodeError> {
+    serde_json::from_value(event.parsed_json.clone())
+        .map_err(|source| DecodeError::Payload { event: name, source })
+}
+
+/// Move `u8`s arrive as numbers, but accept strings like every other integer.
+fn u8_from_str<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u8, D::Error> {
+    let value = u64_from_str(deserializer)?;
+    u8::try_from(value).map_err(serde::de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::sui_rpc::EventId;
+    use serde_json::json;
+
+    const PACKAGE: &str = "0x00000000000000000000000000000000000000000000000000000000000000aa";
+    const ENVELOPE: &str = "0x00000000000000000000000000000000000000000000000000000000000000e1";
+
+    fn event(type_: &str, parsed_json: serde_json::Value) -> SuiEvent {
+        SuiEvent {
+            id: EventId { tx_digest: "digest".to_string(), event_seq: "0".to_string() },
+            timestamp_ms: None,
+            parsed_json,
+            type_: type_.to_string(),
+        }
+    }
+
+    fn claimed(type_: &str) -> SuiEvent {
+        event(type_, json!({ "id": ENVELOPE, "claimer": "0x5", "amount": "7" }))
+    }
+
+    #[test]
+    fn decodes_own_events_in_either_address_form() {
+        let Some(EnvelopeEvent::Claimed(data)) = decode(&claimed("0xaa::sui_red_envelope::EnvelopeClaimed"), PACKAGE).unwrap() else {
+            panic!("expected a claim");
+        };
+        assert_eq!(data.amount, 7);
+        assert_eq!(data.id.as_str(), ENVELOPE);
+
+        let created = event(
+            &format!("{}::sui_red_envelope::EnvelopeCreated<0x2::sui::SUI>", PACKAGE),
+            json!({ "id": ENVELOPE, "owner": "0x5", "amount": "100", "count": "3", "mode": 0, "requires_verification": false }),
+        );
+        let Some(EnvelopeEvent::Created { coin_type, .. }) = decode(&created, PACKAGE).unwrap() else {
+            panic!("expected a creation");
+        };
+        assert_eq!(coin_type.unwrap(), "0x0000000000000000000000000000000000000000000000000000000000000002::sui::SUI");
+    }
+
+    #[test]
+    fn ignores_look_alikes_from_other_packages_and_modules() {
+        for type_ in [
+            "0xbb::sui_red_envelope::EnvelopeClaimed",
+            "0xaa::other_module::EnvelopeClaimed",
+            "0xaa::sui_red_envelope::SomethingElse",
+        ] {
+            assert!(decode(&claimed(type_), PACKAGE).unwrap().is_none(), "{} should be ignored", type_);
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_own_events() {
+        let bad = event("0xaa::sui_red_envelope::EnvelopeClaimed", json!({ "id": ENVELOPE }));
+        assert!(decode(&bad, PACKAGE).is_err());
+        // Another package's payload is none of our business, malformed or not.
+        let foreign = event("0xbb::sui_red_envelope::EnvelopeClaimed", json!({ "id": ENVELOPE }));
+        assert!(decode(&foreign, PACKAGE).unwrap().is_none());
+    }
+}
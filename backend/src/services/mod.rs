@@ -1 +1,5 @@
 pub mod sui_indexer;
+pub mod sui_rpc;
+pub mod reconciler;
+pub mod events;
+pub mod coins;
@@ -0,0 +1,148 @@
+use std::time::Duration;
+use sea_orm::*;
+use tracing::{info, warn, error};
+
+use crate::address::ObjectId;
+use crate::config::NetworkConfig;
+use crate::models::envelopes;
+use crate::services::{coins, sui_indexer};
+use crate::services::sui_rpc::{SuiRpcClient, MULTI_GET_OBJECTS_LIMIT};
+
+/// What older versions of the indexer stored when they couldn't work out an envelope's coin.
+const UNRESOLVED_COIN_TYPE: &str = "Unknown";
+
+/// Outcome of one reconciliation pass over a network.
+#[derive(Debug, Default)]
+pub struct ReconcileReport {
+    pub checked: usize,
+    pub diverged: usize,
+    pub failed: usize,
+    /// Envelopes whose unresolved coin type was read back from chain.
+    pub resolved: usize,
+}
+
+/// Periodically re-reads active envelopes from chain and corrects `remaining_count` and
+/// `is_active` where the event-derived rows drifted (missed events, withdrawals, ...). Each pass
+/// also retries envelopes stored without a coin type.
+pub async fn start_reconciler(db: DatabaseConnection, config: NetworkConfig, rpc: SuiRpcClient, interval: Duration) {
+    info!("Starting envelope reconciler on network: {} every {:?}", config.name, interval);
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+
+        loop {
+            ticker.tick().await;
+
+            match reconcile_network(&db, &config, &rpc).await {
+                Ok(report) => info!(
+                    "[{}] Reconciled {} active envelopes: {} diverged, {} failed, {} coin types resolved",
+                    config.name, report.checked, report.diverged, report.failed, report.resolved
+                ),
+                Err(e) => error!("[{}] Reconciliation failed: {:?}", config.name, e),
+            }
+        }
+    });
+}
+
+pub async fn reconcile_network(db: &DatabaseConnection, config: &NetworkConfig, rpc: &SuiRpcClient) -> anyhow::Result<ReconcileReport> {
+    let mut report = ReconcileReport {
+        resolved: resolve_coin_types(db, config, rpc).await?,
+        ..Default::default()
+    };
+    // Keyset over envelope_id: rows we deactivate drop out of the filter, which would shift offsets.
+    let mut last_id: Option<ObjectId> = None;
+
+    loop {
+        let mut find = envelopes::Entity::find()
+            .filter(envelopes::Column::Network.eq(config.name.as_str()))
+            .filter(envelopes::Column::IsActive.eq(true));
+
+        if let Some(ref id) = last_id {
+            find = find.filter(envelopes::Column::EnvelopeId.gt(id.clone()));
+        }
+
+        let batch = find
+            .order_by_asc(envelopes::Column::EnvelopeId)
+            .limit(MULTI_GET_OBJECTS_LIMIT as u64)
+            .all(db)
+            .await?;
+
+        let Some(last) = batch.last() else {
+            break;
+        };
+        last_id = Some(last.envelope_id.clone());
+
+        let ids: Vec<ObjectId> = batch.iter().map(|e| e.envelope_id.clone()).collect();
+        let objects = rpc.multi_get_objects(&ids, sui_indexer::ENVELOPE_OBJECT_OPTIONS).await?;
+
+        for (envelope, object) in batch.into_iter().zip(objects) {
+            report.checked += 1;
+
+            let parsed = match object.data {
+                Some(data) => sui_indexer::parse_envelope_object(&data, &config.package_id),
+                None => Err(anyhow::anyhow!("Object not found on chain: {:?}", object.error)),
+            };
+
+            let chain = match parsed {
+                Ok(chain) => chain,
+                Err(e) => {
+                    warn!("[{}] Failed to read envelope {} for reconciliation: {:?}", config.name, envelope.envelope_id, e);
+                    report.failed += 1;
+                    continue;
+                }
+            };
+
+            if chain.remaining_count == envelope.remaining_count && chain.is_active == envelope.is_active {
+                continue;
+            }
+
+            warn!(
+                "[{}] Correcting envelope {}: remaining_count {} -> {}, is_active {} -> {}",
+                config.name, envelope.envelope_id,
+                envelope.remaining_count, chain.remaining_count,
+                envelope.is_active, chain.is_active
+            );
+
+            let mut active: envelopes::ActiveModel = envelope.into();
+            active.remaining_count = Set(chain.remaining_count);
+            active.is_active = Set(chain.is_active);
+            active.update(db).await?;
+            report.diverged += 1;
+        }
+    }
+
+    Ok(report)
+}
+
+/// Re-reads the coin type of envelopes stored without one. Envelopes whose object is gone stay
+/// unresolved until a later pass (or a manual sync) can read it.
+async fn resolve_coin_types(db: &DatabaseConnection, config: &NetworkConfig, rpc: &SuiRpcClient) -> anyhow::Result<usize> {
+    let unresolved = envelopes::Entity::find()
+        .filter(envelopes::Column::Network.eq(config.name.as_str()))
+        .filter(envelopes::Column::CoinType.eq(UNRESOLVED_COIN_TYPE))
+        .all(db)
+        .await?;
+
+    let mut resolved = 0;
+    for envelope in unresolved {
+        let coin_type = match sui_indexer::fetch_coin_type(rpc, &config.package_id, &envelope.envelope_id).await {
+            Ok(coin_type) => coin_type,
+            Err(e) => {
+                warn!("[{}] Coin type of envelope {} still unresolved: {:?}", config.name, envelope.envelope_id, e);
+                continue;
+            }
+        };
+
+        info!("[{}] Resolved coin type of envelope {}: {}", config.name, envelope.envelope_id, coin_type);
+        let mut active: envelopes::ActiveModel = envelope.into();
+        active.coin_type = Set(coin_type.clone());
+        active.update(db).await?;
+        resolved += 1;
+
+        if let Err(e) = coins::ensure_coin(db, &config.name, rpc, &coin_type).await {
+            warn!("[{}] Failed to register coin {}: {:?}", config.name, coin_type, e);
+        }
+    }
+
+    Ok(resolved)
+}
@@ -1,6 +1,9 @@
+use std::collections::{HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
+use anyhow::Context;
 use sea_orm::*;
-use serde::{Deserialize, Serialize};
+use sea_orm::sea_query::{Expr, OnConflict};
 use serde_json::json;
 use tokio::time::sleep;
 use rust_decimal::Decimal;
@@ -9,34 +12,204 @@ use futures::{SinkExt, StreamExt};
 use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
 use url::Url;
 
-use crate::config::NetworkConfig;
-use crate::models::{envelopes, claims};
+use crate::address::{self, ObjectId, SuiAddress};
+use crate::config::{IngestionMode, NetworkConfig};
+use crate::models::{envelopes, claims, indexer_state, refunds, dead_letter_events, withdrawal_state};
+use crate::services::coins;
+use crate::services::events::{self, EnvelopeClaimed, EnvelopeCreated, EnvelopeEvent};
+use crate::services::sui_rpc::{
+    EventFilter, EventId, ObjectDataOptions, Page, RpcError, SuiEvent, SuiObjectData, SuiRpcClient,
+    SuiTransactionBlockResponse, TransactionBlockResponseOptions, TransactionFilter, MULTI_GET_OBJECTS_LIMIT,
+};
+use crate::type_tag::{StructTag, TypeTag};
 
-pub async fn start_indexer(db: DatabaseConnection, config: NetworkConfig) {
-    let ws_url = config.ws_url.clone();
-    let rpc_url = config.rpc_url.clone();
-    let package_id = config.package_id.clone();
-    let network = config.name.clone();
+const WITHDRAW_FUNCTION: &str = "withdraw_remaining";
+const SUI_COIN_TYPE: &str = "0x0000000000000000000000000000000000000000000000000000000000000002::sui::SUI";
+const BACKFILL_PAGE_SIZE: u64 = 50;
+const SEEN_EVENTS_CAPACITY: usize = 10_000;
+/// `tx_digest` that manual sync used to store instead of the creation transaction.
+const LEGACY_SYNC_DIGEST: &str = "manual_sync";
 
-    info!("Starting Sui WebIndexer for package: {} on network: {}", package_id, network);
+pub const ENVELOPE_OBJECT_OPTIONS: ObjectDataOptions = ObjectDataOptions {
+    show_type: true,
+    show_content: true,
+    show_previous_transaction: true,
+};
 
-    tokio::spawn(async move {
-        loop {
-            info!("Connecting to WebSocket: {}", ws_url);
-            match connect_and_subscribe(&ws_url, &rpc_url, &package_id, &db, &network).await {
-                Ok(_) => {
-                    warn!("WebSocket connection closed gracefully, reconnecting in 5s...");
+/// Recently handled event ids, shared by the WebSocket and the poller of a network so
+/// neither reprocesses what the other already handled.
+#[derive(Default)]
+struct SeenEvents {
+    order: VecDeque<EventId>,
+    ids: HashSet<EventId>,
+}
+
+impl SeenEvents {
+    fn contains(&self, id: &EventId) -> bool {
+        self.ids.contains(id)
+    }
+
+    fn insert(&mut self, id: EventId) {
+        if self.ids.insert(id.clone()) {
+            self.order.push_back(id);
+            if self.order.len() > SEEN_EVENTS_CAPACITY {
+                if let Some(oldest) = self.order.pop_front() {
+                    self.ids.remove(&oldest);
                 }
+            }
+        }
+    }
+}
+
+type SharedSeenEvents = Arc<Mutex<SeenEvents>>;
+
+pub async fn start_indexer(db: DatabaseConnection, config: NetworkConfig, rpc: SuiRpcClient) {
+    info!(
+        "Starting Sui indexer for package: {} on network: {} ({:?})",
+        config.package_id, config.name, config.ingestion_mode
+    );
+
+    let seen: SharedSeenEvents = Arc::default();
+
+    match config.ingestion_mode {
+        IngestionMode::Websocket => {
+            tokio::spawn(run_websocket(db.clone(), config.clone(), rpc.clone(), seen, true));
+        }
+        IngestionMode::Poll => {
+            tokio::spawn(run_poller(db.clone(), config.clone(), rpc.clone(), seen));
+        }
+        IngestionMode::Both => {
+            // The poller owns the checkpoint and fills any gaps; the socket only lowers latency.
+            tokio::spawn(run_websocket(db.clone(), config.clone(), rpc.clone(), seen.clone(), false));
+            tokio::spawn(run_poller(db.clone(), config.clone(), rpc.clone(), seen));
+        }
+    }
+
+    // Coin types seen before the registry existed; new ones are registered as they arrive.
+    tokio::spawn({
+        let (db, network, rpc) = (db.clone(), config.name.clone(), rpc.clone());
+        async move {
+            if let Err(e) = coins::backfill_coins(&db, &network, &rpc).await {
+                warn!("[{}] Coin registry backfill failed: {:?}", network, e);
+            }
+        }
+    });
+
+    tokio::spawn(run_withdrawal_watcher(db, config, rpc));
+}
+
+/// Where to resume: the saved checkpoint, else the in-memory cursor, else the newest event on chain.
+async fn resume_cursor(
+    db: &DatabaseConnection,
+    config: &NetworkConfig,
+    rpc: &SuiRpcClient,
+    current: Option<EventId>,
+) -> anyhow::Result<Option<EventId>> {
+    if let Some(saved) = load_checkpoint(db, &config.name).await? {
+        return Ok(Some(saved));
+    }
+    if current.is_some() {
+        return Ok(current);
+    }
+    // Never indexed this network: start from the newest event on chain instead of replaying all history.
+    fetch_latest_cursor(rpc, &config.package_id).await
+}
+
+/// Keeps a WebSocket subscription alive. With `owns_checkpoint`, every (re)connect first
+/// backfills from the checkpoint and handled events advance it.
+async fn run_websocket(
+    db: DatabaseConnection,
+    config: NetworkConfig,
+    rpc: SuiRpcClient,
+    seen: SharedSeenEvents,
+    owns_checkpoint: bool,
+) {
+    // Last event we handled, kept across reconnects so each backfill only replays the gap.
+    let mut cursor: Option<EventId> = None;
+
+    loop {
+        if owns_checkpoint {
+            match resume_cursor(&db, &config, &rpc, cursor.clone()).await {
+                Ok(resumed) => cursor = resumed,
                 Err(e) => {
-                    error!("WebSocket error: {:?}, fees reconnecting in 5s...", e);
+                    error!("[{}] Failed to resolve resume cursor: {:?}, retrying in 5s...", config.name, e);
+                    sleep(Duration::from_secs(5)).await;
+                    continue;
                 }
             }
-            sleep(Duration::from_secs(5)).await;
         }
-    });
+
+        let Some(endpoint) = rpc.ws_endpoint() else {
+            error!("[{}] No WebSocket URLs configured, not subscribing", config.name);
+            return;
+        };
+        info!("Connecting to WebSocket: {}", endpoint.url());
+        match connect_and_subscribe(&db, &config, endpoint.url(), &rpc, &seen, &mut cursor, owns_checkpoint).await {
+            Ok(_) => {
+                endpoint.record_success();
+                warn!("WebSocket connection closed gracefully, reconnecting in 5s...");
+            }
+            Err(e) => {
+                endpoint.record_failure();
+                error!("WebSocket error: {:?}, reconnecting in 5s...", e);
+            }
+        }
+        sleep(Duration::from_secs(5)).await;
+    }
+}
+
+/// Calls `suix_queryEvents` every `poll_interval`, continuing from the saved cursor.
+async fn run_poller(db: DatabaseConnection, config: NetworkConfig, rpc: SuiRpcClient, seen: SharedSeenEvents) {
+    let mut cursor: Option<EventId> = None;
+    let mut resumed = false;
+    let mut ticker = tokio::time::interval(config.poll_interval);
+
+    loop {
+        ticker.tick().await;
+
+        if !resumed {
+            match resume_cursor(&db, &config, &rpc, None).await {
+                Ok(c) => {
+                    cursor = c;
+                    resumed = true;
+                }
+                Err(e) => {
+                    error!("[{}] Failed to resolve resume cursor: {:?}", config.name, e);
+                    continue;
+                }
+            }
+        }
+
+        if let Err(e) = backfill_events(&db, &config, &rpc, &seen, &mut cursor).await {
+            error!("[{}] Polling error: {:?}", config.name, e);
+        }
+    }
+}
+
+/// `withdraw_remaining` emits no event, so withdrawals are found by polling the
+/// transactions that call it.
+async fn run_withdrawal_watcher(db: DatabaseConnection, config: NetworkConfig, rpc: SuiRpcClient) {
+    let mut ticker = tokio::time::interval(config.poll_interval);
+
+    loop {
+        ticker.tick().await;
+
+        if let Err(e) = sync_withdrawals(&db, &config, &rpc).await {
+            error!("[{}] Withdrawal sync error: {:?}", config.name, e);
+        }
+    }
 }
 
-async fn connect_and_subscribe(ws_url: &str, rpc_url: &str, package_id: &str, db: &DatabaseConnection, network: &str) -> anyhow::Result<()> {
+async fn connect_and_subscribe(
+    db: &DatabaseConnection,
+    config: &NetworkConfig,
+    ws_url: &str,
+    rpc: &SuiRpcClient,
+    seen: &SharedSeenEvents,
+    cursor: &mut Option<EventId>,
+    owns_checkpoint: bool,
+) -> anyhow::Result<()> {
+    let network = config.name.as_str();
     let url = Url::parse(ws_url)?;
     let (ws_stream, _) = connect_async(url).await?;
     let (mut write, mut read) = ws_stream.split();
@@ -49,13 +222,19 @@ async fn connect_and_subscribe(ws_url: &str, rpc_url: &str, package_id: &str, db
         "id": 1,
         "method": "suix_subscribeEvent",
         "params": [
-            { "MoveModule": { "package": package_id, "module": "sui_red_envelope" } }
+            { "MoveModule": { "package": config.package_id, "module": events::MODULE } }
         ]
     });
 
     info!("[{}] Sending subscription request: {}", network, subscribe_request);
     write.send(Message::Text(subscribe_request.to_string())).await?;
 
+    if owns_checkpoint {
+        // Catch up on everything emitted since the cursor. Live notifications queue up on the
+        // socket meanwhile, so nothing falls between the end of the backfill and the stream.
+        backfill_events(db, config, rpc, seen, cursor).await?;
+    }
+
     while let Some(msg) = read.next().await {
         let msg = msg?;
         if let Message::Text(text) = msg {
@@ -72,7 +251,15 @@ async fn connect_and_subscribe(ws_url: &str, rpc_url: &str, package_id: &str, db
                             // Parse Event
                             match serde_json::from_value::<SuiEvent>(result.clone()) {
                                 Ok(event) => {
-                                    handle_event(db, network, event, rpc_url).await;
+                                    if seen.lock().unwrap().contains(&event.id) {
+                                        info!("[{}] Skipping event already handled: {}", network, event.id.tx_digest);
+                                        continue;
+                                    }
+                                    let id = event.id.clone();
+                                    // On failure the connection is dropped and the next backfill retries from the checkpoint.
+                                    handle_event(db, network, &config.package_id, event, rpc, owns_checkpoint).await?;
+                                    seen.lock().unwrap().insert(id.clone());
+                                    *cursor = Some(id);
                                 }
                                 Err(e) => {
                                     error!("Failed to parse event: {:?}. JSON: {}", e, result);
@@ -92,369 +279,910 @@ async fn connect_and_subscribe(ws_url: &str, rpc_url: &str, package_id: &str, db
     Ok(())
 }
 
-async fn handle_event(db: &DatabaseConnection, network: &str, event: SuiEvent, rpc_url: &str) {
-    if event.type_.contains("::EnvelopeCreated") {
-        info!("Processing EnvelopeCreated: {}", event.id.tx_digest);
-        process_created_event(db, network, event, rpc_url).await;
-    } else if event.type_.contains("::EnvelopeClaimed") {
-        info!("Processing EnvelopeClaimed: {}", event.id.tx_digest);
-        process_claimed_event(db, network, event).await;
+/// Pages through `suix_queryEvents` from `cursor` (exclusive) and handles every event in
+/// ascending order, advancing `cursor` and the saved checkpoint as it goes.
+async fn backfill_events(
+    db: &DatabaseConnection,
+    config: &NetworkConfig,
+    rpc: &SuiRpcClient,
+    seen: &SharedSeenEvents,
+    cursor: &mut Option<EventId>,
+) -> anyhow::Result<()> {
+    let mut handled = 0;
+
+    loop {
+        let page = query_module_events(rpc, &config.package_id, cursor.as_ref(), BACKFILL_PAGE_SIZE, false).await?;
+
+        // Events the socket already handled don't move the checkpoint there (in `Both` mode the
+        // socket doesn't own it), so the page's last one is saved here instead.
+        let mut skipped_to: Option<(EventId, Option<String>)> = None;
+
+        for event in page.data {
+            let id = event.id.clone();
+            if seen.lock().unwrap().contains(&id) {
+                skipped_to = Some((id.clone(), event.timestamp_ms.clone()));
+            } else {
+                handle_event(db, &config.name, &config.package_id, event, rpc, true).await?;
+                seen.lock().unwrap().insert(id.clone());
+                handled += 1;
+                skipped_to = None;
+            }
+            *cursor = Some(id);
+        }
+
+        if let Some((id, timestamp_ms)) = skipped_to {
+            save_checkpoint(db, &config.name, &id, timestamp_ms.as_deref()).await?;
+        }
+
+        if !page.has_next_page {
+            break;
+        }
+    }
+
+    if handled > 0 {
+        info!("[{}] Backfilled {} events", config.name, handled);
     }
+
+    Ok(())
 }
 
+/// Applies withdraw transactions oldest-first, resuming after the last one looked at. Failed
+/// or unrecognisable transactions are stepped over like recorded ones; an error stops the run
+/// before the transaction that caused it, which is retried next time.
+async fn sync_withdrawals(db: &DatabaseConnection, config: &NetworkConfig, rpc: &SuiRpcClient) -> anyhow::Result<()> {
+    let filter = TransactionFilter::MoveFunction {
+        package: config.package_id.clone(),
+        module: Some(events::MODULE.to_string()),
+        function: Some(WITHDRAW_FUNCTION.to_string()),
+    };
+    let options = TransactionBlockResponseOptions {
+        show_input: true,
+        show_effects: true,
+        show_balance_changes: true,
+        ..Default::default()
+    };
+
+    let mut cursor = withdrawal_state::Entity::find_by_id(config.name.clone())
+        .one(db)
+        .await?
+        .map(|state| state.tx_digest);
+
+    loop {
+        let page = rpc.query_transaction_blocks(filter.clone(), options, cursor.as_deref(), BACKFILL_PAGE_SIZE, false).await?;
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
-struct SuiEventCursor {
-    #[serde(rename = "txDigest")]
-    tx_digest: String,
-    #[serde(rename = "eventSeq")]
-    event_seq: String,
+        for tx in &page.data {
+            if refunds::Entity::find_by_id(tx.digest.clone()).one(db).await?.is_none() {
+                process_withdrawal(db, &config.name, &config.package_id, rpc, tx).await?;
+            }
+            save_withdrawal_cursor(db, &config.name, &tx.digest).await?;
+            cursor = Some(tx.digest.clone());
+        }
+
+        if !page.has_next_page || page.data.is_empty() {
+            return Ok(());
+        }
+    }
 }
 
-#[derive(Debug, Deserialize)]
-struct SuiEvent {
-    id: SuiEventId, 
-    #[serde(rename = "timestampMs")]
-    timestamp_ms: Option<String>,
-    #[serde(rename = "parsedJson")]
-    parsed_json: serde_json::Value,
-    #[serde(rename = "type")]
-    type_: String,
+async fn save_withdrawal_cursor(db: &DatabaseConnection, network: &str, tx_digest: &str) -> Result<(), DbErr> {
+    let model = withdrawal_state::ActiveModel {
+        network: Set(network.to_string()),
+        tx_digest: Set(tx_digest.to_string()),
+        updated_at: Set(chrono::Utc::now().naive_utc()),
+    };
+
+    withdrawal_state::Entity::insert(model)
+        .on_conflict(
+            OnConflict::column(withdrawal_state::Column::Network)
+                .update_columns([withdrawal_state::Column::TxDigest, withdrawal_state::Column::UpdatedAt])
+                .to_owned(),
+        )
+        .exec_without_returning(db)
+        .await?;
+
+    Ok(())
 }
 
-#[derive(Debug, Deserialize)]
-struct SuiEventId {
-    #[serde(rename = "txDigest")]
-    tx_digest: String,
-    // seq unused
+async fn process_withdrawal(
+    db: &DatabaseConnection,
+    network: &str,
+    package_id: &str,
+    rpc: &SuiRpcClient,
+    tx: &SuiTransactionBlockResponse,
+) -> anyhow::Result<()> {
+    let digest = tx.digest.as_str();
+
+    if let Some(effects) = tx.effects.as_ref().filter(|e| !e.status.is_success()) {
+        info!("[{}] Skipping failed withdraw transaction {}: {:?}", network, digest, effects.status.error);
+        return Ok(());
+    }
+
+    let Some(data) = tx.transaction.as_ref().map(|t| &t.data) else {
+        warn!("[{}] Withdraw transaction {} has no input data", network, digest);
+        return Ok(());
+    };
+
+    let Some(envelope_id) = withdrawn_envelope_id(&data.transaction, package_id) else {
+        warn!("[{}] No withdraw_remaining call found in {}", network, digest);
+        return Ok(());
+    };
+
+    let find_envelope = || envelopes::Entity::find()
+        .filter(envelopes::Column::EnvelopeId.eq(envelope_id.clone()))
+        .filter(envelopes::Column::Network.eq(network))
+        .one(db);
+
+    let envelope = match find_envelope().await? {
+        Some(env) => env,
+        None => {
+            info!("Envelope {} not found for withdrawal {}, syncing envelope first...", envelope_id, digest);
+            sync_envelope_by_id(db, network, package_id, rpc, &envelope_id).await?;
+            find_envelope().await?
+                .ok_or_else(|| anyhow::anyhow!("Envelope {} missing after sync", envelope_id))?
+        }
+    };
+
+    let amount = refunded_amount(tx, &data.sender, &envelope.coin_type);
+
+    let refund = refunds::ActiveModel {
+        refund_id: Set(digest.to_string()),
+        envelope_id: Set(envelope_id.clone()),
+        network: Set(network.to_string()),
+        owner: Set(data.sender.clone()),
+        amount: Set(amount),
+        refunded_at: Set(chain_time(tx.timestamp_ms.as_deref())),
+    };
+
+    let txn = db.begin().await?;
+    refunds::Entity::insert(refund).exec_without_returning(&txn).await?;
+
+    let mut active: envelopes::ActiveModel = envelope.into();
+    active.remaining_count = Set(0);
+    active.is_active = Set(false);
+    active.update(&txn).await?;
+    txn.commit().await?;
+
+    info!("[{}] Recorded withdrawal of {} from envelope {}", network, amount, envelope_id);
+    Ok(())
 }
 
-async fn fetch_coin_type(rpc_url: &str, object_id: &str) -> String {
-    let client = reqwest::Client::new();
-    let query = json!({
-        "jsonrpc": "2.0",
-        "id": 1,
-        "method": "sui_getObject",
-        "params": [
-            object_id,
-            { "showType": true }
-        ]
-    });
+/// Object id passed to the first `withdraw_remaining` call of `package_id` in a programmable
+/// transaction.
+fn withdrawn_envelope_id(ptb: &serde_json::Value, package_id: &str) -> Option<ObjectId> {
+    let mut calls = ptb["transactions"].as_array()?
+        .iter()
+        .filter_map(|t| t.get("MoveCall"))
+        .filter(|c| c["package"].as_str().and_then(|p| address::normalize(p).ok()).as_deref() == Some(package_id))
+        .filter(|c| c["module"] == events::MODULE && c["function"] == WITHDRAW_FUNCTION);
 
-    match client.post(rpc_url).json(&query).send().await {
-        Ok(res) => {
-            if let Ok(json) = res.json::<serde_json::Value>().await {
-                let type_str = json["result"]["data"]["type"].as_str()
-                    .or_else(|| json["result"]["type"].as_str());
-
-                if let Some(t) = type_str {
-                    // Use a more robust regex or loop to find the inner type
-                    // e.g. "0x...::RedEnvelope<0x...::SUI>" -> "0x...::SUI"
-                    // Find the first '<' and the last '>'
-                    if let (Some(start), Some(end)) = (t.find('<'), t.rfind('>')) {
-                        return t[start + 1..end].to_string();
-                    }
+    let call = calls.next()?;
+    if calls.next().is_some() {
+        // Balance changes can't be split per envelope; the reconciler deactivates the others.
+        warn!("Transaction withdraws several envelopes, only the first is recorded");
+    }
+
+    let input = call["arguments"][0]["Input"].as_u64()? as usize;
+    ptb["inputs"][input]["objectId"].as_str()?.parse().ok()
+}
+
+/// What the owner got back: their balance change in the envelope's coin, plus the gas they
+/// paid when that coin is SUI.
+fn refunded_amount(tx: &SuiTransactionBlockResponse, owner: &SuiAddress, coin_type: &str) -> Decimal {
+    let change: i128 = tx.balance_changes.iter()
+        .flatten()
+        .filter(|c| c.address_owner().as_ref() == Some(owner))
+        .filter(|c| same_coin_type(&c.coin_type, coin_type))
+        .filter_map(|c| c.amount.parse::<i128>().ok())
+        .sum();
+
+    let gas: i128 = if same_coin_type(coin_type, SUI_COIN_TYPE) {
+        tx.effects.as_ref().map(|e| e.gas_used.net_cost()).unwrap_or(0)
+    } else {
+        0
+    };
+
+    Decimal::from((change + gas).max(0))
+}
+
+/// Compares coin types by their parsed tags, so `0x2::sui::SUI` matches its long form.
+fn same_coin_type(a: &str, b: &str) -> bool {
+    match (a.parse::<TypeTag>(), b.parse::<TypeTag>()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Cursor of the most recent event emitted by the module, if any.
+async fn fetch_latest_cursor(rpc: &SuiRpcClient, package_id: &str) -> anyhow::Result<Option<EventId>> {
+    let page = query_module_events(rpc, package_id, None, 1, true).await?;
+    Ok(page.data.into_iter().next().map(|event| event.id))
+}
+
+async fn query_module_events(
+    rpc: &SuiRpcClient,
+    package_id: &str,
+    cursor: Option<&EventId>,
+    limit: u64,
+    descending: bool,
+) -> anyhow::Result<Page<SuiEvent, EventId>> {
+    let filter = EventFilter::MoveModule {
+        package: package_id.to_string(),
+        module: events::MODULE.to_string(),
+    };
+    Ok(rpc.query_events(filter, cursor, limit, descending).await?)
+}
+
+/// Processes one event and, with `advance_checkpoint`, moves the network checkpoint in the same
+/// transaction, so the checkpoint never points past an event whose rows were not written.
+///
+/// An event that fails to decode or to apply is rolled back and parked in `dead_letter_events`
+/// instead, so one bad event can't stall the indexer. Only failing to commit the outcome (e.g.
+/// the database being down) is returned.
+async fn handle_event(
+    db: &DatabaseConnection,
+    network: &str,
+    package_id: &str,
+    event: SuiEvent,
+    rpc: &SuiRpcClient,
+    advance_checkpoint: bool,
+) -> Result<(), DbErr> {
+    let (txn, created_coin) = match apply_event(db, network, package_id, &event, rpc).await {
+        Ok(applied) => applied,
+        Err(e) => {
+            error!("[{}] Failed to process event {}: {:?}", network, event.id.tx_digest, e);
+
+            let txn = db.begin().await?;
+            match record_dead_letter(&txn, network, &event, &e.to_string()).await {
+                Ok(()) => (txn, None),
+                // Retrying would fail the same way forever and hold up every later event, so
+                // the event is logged in full and stepped over. If the database itself is
+                // down, saving the checkpoint below fails too and the event is retried.
+                Err(dead_letter_err) => {
+                    txn.rollback().await?;
+                    error!(
+                        "[{}] Failed to dead-letter event {}: {:?}, skipping it: {}",
+                        network,
+                        event.id.tx_digest,
+                        dead_letter_err,
+                        serde_json::to_string(&event).unwrap_or_else(|_| format!("{:?}", event)),
+                    );
+                    (db.begin().await?, None)
                 }
             }
         }
-        Err(e) => error!("RPC call failed: {:?}", e),
+    };
+
+    if advance_checkpoint {
+        save_checkpoint(&txn, network, &event.id, event.timestamp_ms.as_deref()).await?;
     }
-    "Unknown".to_string()
+    txn.commit().await?;
+
+    if let Some(coin_type) = created_coin {
+        register_coin(db, network, rpc, &coin_type).await;
+    }
+    Ok(())
 }
 
-async fn process_created_event(db: &DatabaseConnection, network: &str, event: SuiEvent, rpc_url: &str) {
-    use crate::models::envelopes::ActiveModel;
-    
-    let json = &event.parsed_json;
-    // Fields: id, owner, amount, count, mode
-    let id_str = json["id"].as_str().unwrap_or_default();
-    let owner = json["owner"].as_str().unwrap_or_default();
-    let amount_str = json["amount"].as_str().unwrap_or("0");
-    let count_u64 = json["count"].as_str().unwrap_or("0").parse::<i64>().unwrap_or(0);
-    // mode could be integer or string depending on SDK? Usually integer for u8
-    let mode_val = &json["mode"];
-    let mode_u8 = if let Some(s) = mode_val.as_str() {
-        s.parse::<i16>().unwrap_or(0)
-    } else {
-        mode_val.as_u64().unwrap_or(0) as i16
+/// Decodes `event` and makes the RPC calls it needs, then writes it in a transaction that is
+/// returned uncommitted, so the caller can add to it. Also returns the coin type of a created
+/// envelope, to be registered once the transaction is committed.
+async fn apply_event(
+    db: &DatabaseConnection,
+    network: &str,
+    package_id: &str,
+    event: &SuiEvent,
+    rpc: &SuiRpcClient,
+) -> anyhow::Result<(DatabaseTransaction, Option<String>)> {
+    let mut decoded = events::decode(event, package_id)?;
+    if let Some(EnvelopeEvent::Created { data, coin_type }) = &mut decoded {
+        *coin_type = Some(resolve_coin_type(rpc, package_id, data, coin_type.take()).await?);
+    }
+
+    let txn = db.begin().await?;
+    let written: anyhow::Result<Option<String>> = async {
+        match decoded {
+            Some(EnvelopeEvent::Created { data, coin_type: Some(coin_type) }) => {
+                info!("Processing EnvelopeCreated: {}", event.id.tx_digest);
+                process_created_event(&txn, network, event, data, coin_type.clone()).await?;
+                Ok(Some(coin_type))
+            }
+            Some(EnvelopeEvent::Claimed(data)) => {
+                info!("Processing EnvelopeClaimed: {}", event.id.tx_digest);
+                process_claimed_event(&txn, network, event, data).await?;
+                Ok(None)
+            }
+            Some(EnvelopeEvent::Created { coin_type: None, .. }) => unreachable!("coin type resolved above"),
+            None => Ok(None),
+        }
+    }.await;
+
+    match written {
+        Ok(created_coin) => Ok((txn, created_coin)),
+        Err(e) => {
+            txn.rollback().await?;
+            Err(e)
+        }
+    }
+}
+
+/// Re-applies a dead-lettered event. On success the row is deleted in the same transaction;
+/// on failure it is kept with the new error.
+pub async fn replay_dead_letter(
+    db: &DatabaseConnection,
+    package_id: &str,
+    rpc: &SuiRpcClient,
+    dead_letter: dead_letter_events::Model,
+) -> anyhow::Result<()> {
+    let event: SuiEvent = serde_json::from_str(&dead_letter.payload)?;
+    let network = dead_letter.network.clone();
+
+    match apply_event(db, &network, package_id, &event, rpc).await {
+        Ok((txn, created_coin)) => {
+            dead_letter_events::Entity::delete_by_id(dead_letter.id).exec(&txn).await?;
+            txn.commit().await?;
+            info!("[{}] Replayed dead-lettered event {}", network, event.id.tx_digest);
+
+            if let Some(coin_type) = created_coin {
+                register_coin(db, &network, rpc, &coin_type).await;
+            }
+            Ok(())
+        }
+        Err(e) => {
+            let replay_count = dead_letter.replay_count + 1;
+            let mut active: dead_letter_events::ActiveModel = dead_letter.into();
+            active.error = Set(e.to_string());
+            active.replay_count = Set(replay_count);
+            active.last_replayed_at = Set(Some(chrono::Utc::now().naive_utc()));
+            active.update(db).await?;
+            Err(e)
+        }
+    }
+}
+
+async fn record_dead_letter<C: ConnectionTrait>(db: &C, network: &str, event: &SuiEvent, error: &str) -> Result<(), DbErr> {
+    let payload = serde_json::to_string(event)
+        .map_err(|e| DbErr::Custom(format!("Failed to serialize event: {}", e)))?;
+
+    let model = dead_letter_events::ActiveModel {
+        id: NotSet,
+        network: Set(network.to_string()),
+        tx_digest: Set(event.id.tx_digest.clone()),
+        event_seq: Set(event.id.event_seq.clone()),
+        event_type: Set(event.type_.clone()),
+        payload: Set(payload),
+        error: Set(error.to_string()),
+        replay_count: Set(0),
+        last_replayed_at: Set(None),
+        created_at: Set(chrono::Utc::now().naive_utc()),
     };
 
-    let requires_verification = json["requires_verification"].as_bool().unwrap_or(false);
+    dead_letter_events::Entity::insert(model).exec(db).await?;
+    Ok(())
+}
 
-    let ts = event.timestamp_ms.as_deref().unwrap_or("0").parse::<i64>().unwrap_or(0);
+async fn load_checkpoint(db: &DatabaseConnection, network: &str) -> Result<Option<EventId>, DbErr> {
+    let state = indexer_state::Entity::find_by_id(network.to_string()).one(db).await?;
 
-    // Convert timestamp
-    let created_at = chrono::DateTime::from_timestamp_millis(ts)
-        .map(|dt| dt.naive_utc())
-        .unwrap_or_default();
+    Ok(state.map(|s| EventId {
+        tx_digest: s.tx_digest,
+        event_seq: s.event_seq,
+    }))
+}
 
-    let amount_dec = Decimal::from_str_exact(amount_str).unwrap_or_default();
+async fn save_checkpoint<C: ConnectionTrait>(db: &C, network: &str, cursor: &EventId, timestamp_ms: Option<&str>) -> Result<(), DbErr> {
+    let model = indexer_state::ActiveModel {
+        network: Set(network.to_string()),
+        tx_digest: Set(cursor.tx_digest.clone()),
+        event_seq: Set(cursor.event_seq.clone()),
+        event_timestamp: Set(chain_time(timestamp_ms)),
+        updated_at: Set(chrono::Utc::now().naive_utc()),
+    };
 
-    // extract Generic T from type string
-    // e.g. 0x...::mod::EnvelopeCreated<0x...::sui::SUI>
-    let mut coin_type = event.type_.split('<').nth(1).map(|s| s.trim_end_matches('>')).unwrap_or("Unknown").to_string();
+    indexer_state::Entity::insert(model)
+        .on_conflict(
+            OnConflict::column(indexer_state::Column::Network)
+                .update_columns([
+                    indexer_state::Column::TxDigest,
+                    indexer_state::Column::EventSeq,
+                    indexer_state::Column::EventTimestamp,
+                    indexer_state::Column::UpdatedAt,
+                ])
+                .to_owned(),
+        )
+        .exec_without_returning(db)
+        .await?;
 
-    if coin_type == "Unknown" {
-        info!("Coin type unknown from event, fetching object {} from RPC...", id_str);
-        // Note: Object might not be immediately available if we are VERY fast (indexer lag), but usually ok.
-        let fetched = fetch_coin_type(rpc_url, id_str).await;
-        if fetched != "Unknown" {
-             coin_type = fetched;
-             info!("Resolved coin type: {}", coin_type);
-        }
+    Ok(())
+}
+
+/// Reads `T` off the envelope object itself, for events whose type tag didn't carry it.
+pub async fn fetch_coin_type(rpc: &SuiRpcClient, package_id: &str, object_id: &ObjectId) -> anyhow::Result<String> {
+    let options = ObjectDataOptions { show_type: true, ..Default::default() };
+    let data = rpc.get_object_data(object_id, options).await?;
+    Ok(envelope_coin_type(data.type_.as_deref().unwrap_or_default(), package_id)?)
+}
+
+/// An object that exists on chain but isn't a `RedEnvelope<T>`.
+#[derive(Debug, thiserror::Error)]
+#[error("Invalid object type: {0}")]
+pub struct NotAnEnvelope(pub String);
+
+/// The canonical `T` of a `RedEnvelope<T>` object type declared by `package_id`.
+fn envelope_coin_type(object_type: &str, package_id: &str) -> Result<String, NotAnEnvelope> {
+    let tag: Option<StructTag> = object_type.parse().ok();
+    match tag.as_ref().filter(|t| events::is_own_type(t, package_id)).map(|t| (t.name.as_str(), t.type_params.as_slice())) {
+        Some(("RedEnvelope", [coin_type])) => Ok(coin_type.to_string()),
+        _ => Err(NotAnEnvelope(object_type.to_string())),
+    }
+}
+
+/// `T` of a created envelope: from the event type tag when it carried one, otherwise read off
+/// the object. Called before the event's transaction is opened.
+async fn resolve_coin_type(rpc: &SuiRpcClient, package_id: &str, data: &EnvelopeCreated, coin_type: Option<String>) -> anyhow::Result<String> {
+    if let Some(coin_type) = coin_type {
+        return Ok(coin_type);
     }
 
+    info!("Coin type unknown from event, fetching object {} from RPC...", data.id);
+    // Failing here dead-letters the event, so it is resolved again on replay rather than
+    // stored without a coin type.
+    let fetched = fetch_coin_type(rpc, package_id, &data.id).await
+        .with_context(|| format!("Failed to resolve coin type of envelope {}", data.id))?;
+    info!("Resolved coin type: {}", fetched);
+    Ok(fetched)
+}
+
+/// Metadata is only for display; a failed lookup is retried with the next envelope of this coin.
+async fn register_coin(db: &DatabaseConnection, network: &str, rpc: &SuiRpcClient, coin_type: &str) {
+    if let Err(e) = coins::ensure_coin(db, network, rpc, coin_type).await {
+        warn!("[{}] Failed to register coin {}: {:?}", network, coin_type, e);
+    }
+}
+
+async fn process_created_event<C: ConnectionTrait>(
+    db: &C,
+    network: &str,
+    event: &SuiEvent,
+    data: EnvelopeCreated,
+    coin_type: String,
+) -> Result<(), DbErr> {
+    use crate::models::envelopes::ActiveModel;
+
+    let created_at = event_time(event);
+    let count = data.count as i64;
+
     let model = ActiveModel {
-        envelope_id: Set(id_str.to_string()),
+        envelope_id: Set(data.id.clone()),
         network: Set(network.to_string()),
-        owner: Set(owner.to_string()),
+        owner: Set(data.owner),
         coin_type: Set(coin_type),
-        total_amount: Set(amount_dec),
-        total_count: Set(count_u64),
-        mode: Set(mode_u8),
-        remaining_count: Set(count_u64), // Initially full
+        total_amount: Set(Decimal::from(data.amount)),
+        total_count: Set(count),
+        mode: Set(data.mode as i16),
+        remaining_count: Set(count), // Initially full
         is_active: Set(true),
-        requires_verification: Set(requires_verification),
+        requires_verification: Set(data.requires_verification),
         created_at: Set(created_at),
-        tx_digest: Set(event.id.tx_digest),
+        tx_digest: Set(event.id.tx_digest.clone()),
     };
 
     if let Err(e) = envelopes::Entity::insert(model).exec(db).await {
         // Ignore dupe errors
-        if !e.to_string().contains("Duplicate entry") {
+        if !matches!(e.sql_err(), Some(SqlErr::UniqueConstraintViolation(_))) {
             error!("Failed to insert envelope: {:?}", e);
-        } else {
-            warn!("Envelope {} already exists", id_str);
+            return Err(e);
         }
+        warn!("Envelope {} already exists", data.id);
     }
+
+    Ok(())
 }
 
-async fn process_claimed_event(db: &DatabaseConnection, network: &str, event: SuiEvent) {
-    use crate::models::claims::ActiveModel as ClaimActiveModel;
-    
-    let json = &event.parsed_json;
-    // Fields: id, claimer, amount
-    let env_id = json["id"].as_str().unwrap_or_default();
-    let claimer = json["claimer"].as_str().unwrap_or_default();
-    let amount_str = json["amount"].as_str().unwrap_or("0");
-    let ts = event.timestamp_ms.as_deref().unwrap_or("0").parse::<i64>().unwrap_or(0);
-    let claimed_at = chrono::DateTime::from_timestamp_millis(ts)
+/// Checkpoint time of an event.
+fn event_time(event: &SuiEvent) -> chrono::NaiveDateTime {
+    chain_time(event.timestamp_ms.as_deref())
+}
+
+/// A `timestampMs` as reported by the node; the epoch when it is missing or malformed.
+fn chain_time(timestamp_ms: Option<&str>) -> chrono::NaiveDateTime {
+    let ts = timestamp_ms.unwrap_or("0").parse::<i64>().unwrap_or(0);
+    chrono::DateTime::from_timestamp_millis(ts)
         .map(|dt| dt.naive_utc())
-        .unwrap_or_default();
+        .unwrap_or_default()
+}
+
+/// Inserts the claim and decrements the envelope in one transaction. A claim is identified by
+/// (network, tx_digest, event_seq), so replays from reconnects, backfill or manual sync are no-ops.
+/// Returns whether the claim was new.
+async fn process_claimed_event<C: ConnectionTrait + TransactionTrait>(
+    db: &C,
+    network: &str,
+    event: &SuiEvent,
+    data: EnvelopeClaimed,
+) -> Result<bool, DbErr> {
+    use crate::models::claims::ActiveModel as ClaimActiveModel;
+
+    let env_id = &data.id;
+    let claimer = &data.claimer;
+    let claimed_at = event_time(event);
     
-    let amount_dec = Decimal::from_str_exact(amount_str).unwrap_or_default();
+    // Nested inside the caller's transaction when there is one (as a savepoint).
+    let txn = db.begin().await?;
+
+    let existing = claims::Entity::find()
+        .filter(claims::Column::Network.eq(network))
+        .filter(claims::Column::TxDigest.eq(event.id.tx_digest.as_str()))
+        .filter(claims::Column::EventSeq.eq(event.id.event_seq.as_str()))
+        .one(&txn)
+        .await?;
+
+    if existing.is_some() {
+        warn!("Claim for env {} by {} already processed", env_id, claimer);
+        return Ok(false);
+    }
 
     let claim = ClaimActiveModel {
         claim_id: NotSet, // Auto incr
-        envelope_id: Set(env_id.to_string()),
+        envelope_id: Set(env_id.clone()),
         network: Set(network.to_string()),
-        claimer: Set(claimer.to_string()),
-        amount: Set(amount_dec),
+        claimer: Set(claimer.clone()),
+        amount: Set(Decimal::from(data.amount)),
         claimed_at: Set(claimed_at),
         tx_digest: Set(event.id.tx_digest.clone()),
+        event_seq: Set(event.id.event_seq.clone()),
     };
 
-    match claims::Entity::insert(claim).exec(db).await {
+    match claims::Entity::insert(claim).exec(&txn).await {
         Ok(_) => {
-            if let Err(e) = update_envelope_decrement(db, env_id, network).await {
+            if let Err(e) = update_envelope_decrement(&txn, env_id, network).await {
                 error!("Failed to decrement envelope count: {:?}", e);
+                return Err(e);
             }
         }
+        // Lost a race with a concurrent insert of the same event.
+        Err(e) if matches!(e.sql_err(), Some(SqlErr::UniqueConstraintViolation(_))) => {
+            warn!("Claim for env {} by {} already processed", env_id, claimer);
+            return Ok(false);
+        }
         Err(e) => {
-            if !e.to_string().contains("Duplicate entry") {
-               error!("Failed to insert claim: {:?}", e);
-            } else {
-                warn!("Claim for env {} by {} already processed", env_id, claimer);
-            }
+            error!("Failed to insert claim: {:?}", e);
+            return Err(e);
         }
     }
+
+    txn.commit().await?;
+    Ok(true)
 }
 
-pub async fn sync_envelope_by_id(db: &DatabaseConnection, network: &str, rpc_url: &str, object_id: &str) -> anyhow::Result<()> {
-    use crate::models::envelopes::ActiveModel;
-    
-    info!("Manually syncing envelope {} on network {}", object_id, network);
+/// The mutable state of a `RedEnvelope<T>` object as read from chain. Everything else is fixed
+/// at creation and comes from the `EnvelopeCreated` event.
+#[derive(Debug, Clone)]
+pub struct ChainEnvelope {
+    pub remaining_count: i64,
+    pub is_active: bool,
+}
 
-    let client = reqwest::Client::new();
-    let query = json!({
-        "jsonrpc": "2.0",
-        "id": 1,
-        "method": "sui_getObject",
-        "params": [
-            object_id,
-            { "showContent": true, "showType": true }
-        ]
-    });
+/// Parses a `RedEnvelope<T>` fetched with [`ENVELOPE_OBJECT_OPTIONS`].
+pub fn parse_envelope_object(data: &SuiObjectData, package_id: &str) -> anyhow::Result<ChainEnvelope> {
+    envelope_coin_type(data.type_.as_deref().unwrap_or_default(), package_id)?;
+
+    let content = data.content.as_ref()
+        .map(|c| &c.fields)
+        .ok_or_else(|| anyhow::anyhow!("Object {} has no content", data.object_id))?;
+    // A field we can't read fails the parse: defaulting to 0 would deactivate the envelope.
+    let remaining_count = u64_field(content, "remaining_count")
+        .with_context(|| format!("Envelope {}", data.object_id))?;
+    let current_balance = u64_field(content, "balance")
+        .with_context(|| format!("Envelope {}", data.object_id))?;
 
-    let res = client.post(rpc_url).json(&query).send().await?;
-    let json: serde_json::Value = res.json().await?;
-    
-    // Check if error
-    if let Some(err) = json.get("error") {
-        return Err(anyhow::anyhow!("RPC Error during sync_envelope_by_id: {:?}", err));
-    }
-    
-    let data = &json["result"]["data"];
-    if data.is_null() {
-        return Err(anyhow::anyhow!("Object not found on chain: {}", object_id));
-    }
-
-    let type_str = data["type"].as_str().unwrap_or_default();
-    if !type_str.contains("::RedEnvelope<") {
-        return Err(anyhow::anyhow!("Invalid object type: {}", type_str));
-    }
-
-    let content = &data["content"]["fields"];
-    let owner = content["owner"].as_str().unwrap_or_default();
-    let total_amount = content["total_amount"].as_str().unwrap_or("0");
-    let total_count = content["total_count"].as_str().unwrap_or("0").parse::<i64>().unwrap_or(0);
-    let remaining_count = content["remaining_count"].as_str().unwrap_or("0").parse::<i64>().unwrap_or(0);
-    let mode = content["mode"].as_u64().unwrap_or(0) as i16;
-    let requires_verification = content["requires_verification"].as_bool().unwrap_or(false);
-
-    // Extract current balance to determine if active
-    // Balance<T> is usually represented as { fields: { value: "..." } } or just value depending on context
-    // We try to parse it safely
-    let current_balance = if let Some(val) = content["balance"].as_u64() {
-        val
-    } else if let Some(str_val) = content["balance"].as_str() {
-        str_val.parse::<u64>().unwrap_or(0)
-    } else {
-         content["balance"].get("fields")
-            .and_then(|f| f.get("value"))
-            .and_then(|v| v.as_str())
-            .map(|s| s.parse::<u64>().unwrap_or(0))
-            .unwrap_or(0)
-    };
-    
     // Determine status: Active if remaining > 0 AND has balance
     let is_active = remaining_count > 0 && current_balance > 0;
+    let remaining_count = i64::try_from(remaining_count)?;
 
-    // Extract coin type
-    let coin_type = if let (Some(start), Some(end)) = (type_str.find('<'), type_str.rfind('>')) {
-        type_str[start + 1..end].to_string()
-    } else {
-        "Unknown".to_string()
+    Ok(ChainEnvelope { remaining_count, is_active })
+}
+
+/// A `u64` field of an object's content: a string, a number, or a `Balance<T>` rendered as
+/// `{ "fields": { "value": .. } }`.
+fn u64_field(content: &serde_json::Value, name: &str) -> anyhow::Result<u64> {
+    let value = &content[name];
+    let value = value.get("fields").and_then(|f| f.get("value")).unwrap_or(value);
+    let parsed = match value {
+        serde_json::Value::String(s) => s.parse().ok(),
+        serde_json::Value::Number(n) => n.as_u64(),
+        _ => None,
     };
+    parsed.ok_or_else(|| anyhow::anyhow!("Unreadable {} field: {}", name, value))
+}
 
-    let amount_dec = Decimal::from_str_exact(total_amount).unwrap_or_default();
+/// Outcome of [`sync_envelope_by_id`].
+#[derive(Debug, Default)]
+pub struct EnvelopeSync {
+    /// Claims that were missing and have been imported.
+    pub claims_added: usize,
+}
 
-    // Upsert logic
-    let model = ActiveModel {
-        envelope_id: Set(object_id.to_string()),
-        network: Set(network.to_string()),
-        owner: Set(owner.to_string()),
-        coin_type: Set(coin_type),
-        total_amount: Set(amount_dec),
-        total_count: Set(total_count),
-        mode: Set(mode),
-        remaining_count: Set(remaining_count),
-        is_active: Set(is_active),
-        requires_verification: Set(requires_verification),
-        // Keep existing created_at or use current if missing
-        created_at: Set(chrono::Utc::now().naive_utc()), 
-        tx_digest: Set("manual_sync".to_string()),
-    };
+/// Upserts an envelope from chain. Unknown envelopes are inserted from their `EnvelopeCreated`
+/// event, exactly as the live indexer would have, missing claims are imported from the
+/// envelope's transaction history, and the counters are then taken from the object.
+pub async fn sync_envelope_by_id(
+    db: &DatabaseConnection,
+    network: &str,
+    package_id: &str,
+    rpc: &SuiRpcClient,
+    object_id: &ObjectId,
+) -> anyhow::Result<EnvelopeSync> {
+    info!("Manually syncing envelope {} on network {}", object_id, network);
+
+    let data = rpc.get_object_data(object_id, ENVELOPE_OBJECT_OPTIONS).await?;
+    sync_envelope_object(db, network, package_id, rpc, &data, true).await
+}
+
+/// Like [`sync_envelope_by_id`], but without walking the claim history: for callers that only
+/// need the envelope row and must not spend an unbounded number of RPC calls on it.
+pub async fn insert_envelope_by_id(
+    db: &DatabaseConnection,
+    network: &str,
+    package_id: &str,
+    rpc: &SuiRpcClient,
+    object_id: &ObjectId,
+) -> anyhow::Result<()> {
+    info!("Inserting envelope {} on network {} from chain", object_id, network);
+
+    let data = rpc.get_object_data(object_id, ENVELOPE_OBJECT_OPTIONS).await?;
+    sync_envelope_object(db, network, package_id, rpc, &data, false).await?;
+    Ok(())
+}
+
+/// [`sync_envelope_by_id`] for many envelopes, fetched [`MULTI_GET_OBJECTS_LIMIT`] at a time with
+/// `sui_multiGetObjects`. Returns one result per id, in order; a failed batch fails each of its ids.
+pub async fn sync_envelopes(
+    db: &DatabaseConnection,
+    network: &str,
+    package_id: &str,
+    rpc: &SuiRpcClient,
+    object_ids: &[ObjectId],
+) -> Vec<(ObjectId, anyhow::Result<EnvelopeSync>)> {
+    info!("Manually syncing {} envelopes on network {}", object_ids.len(), network);
+    let mut results = Vec::with_capacity(object_ids.len());
+
+    for batch in object_ids.chunks(MULTI_GET_OBJECTS_LIMIT) {
+        let objects = match rpc.multi_get_objects(batch, ENVELOPE_OBJECT_OPTIONS).await {
+            Ok(objects) => objects,
+            Err(e) => {
+                error!("[{}] Failed to fetch envelopes for sync: {:?}", network, e);
+                results.extend(batch.iter().map(|id| (id.clone(), Err(anyhow::anyhow!("{:#}", e)))));
+                continue;
+            }
+        };
+
+        for (object_id, object) in batch.iter().zip(objects) {
+            let result = match object.data {
+                Some(data) => sync_envelope_object(db, network, package_id, rpc, &data, true).await,
+                None => Err(RpcError::ObjectNotFound(object_id.to_string()).into()),
+            };
+            if let Err(ref e) = result {
+                warn!("[{}] Failed to sync envelope {}: {:?}", network, object_id, e);
+            }
+            results.push((object_id.clone(), result));
+        }
+    }
+
+    results
+}
+
+/// The upsert behind [`sync_envelope_by_id`], for an object fetched with [`ENVELOPE_OBJECT_OPTIONS`].
+/// Missing claims are only imported with `import_history`.
+async fn sync_envelope_object(
+    db: &DatabaseConnection,
+    network: &str,
+    package_id: &str,
+    rpc: &SuiRpcClient,
+    data: &SuiObjectData,
+    import_history: bool,
+) -> anyhow::Result<EnvelopeSync> {
+    let object_id = &data.object_id;
+    let chain = parse_envelope_object(data, package_id)?;
 
-    // We use a custom query to update if exists, or insert if not
-    // In SeaORM we can use OnConflict but it varies by DB.
-    // Simpler: check if exists
     let existing = envelopes::Entity::find()
-        .filter(envelopes::Column::EnvelopeId.eq(object_id))
+        .filter(envelopes::Column::EnvelopeId.eq(object_id.clone()))
         .filter(envelopes::Column::Network.eq(network))
         .one(db)
         .await?;
 
-    if let Some(env) = existing {
-        let mut active: ActiveModel = env.into();
-        active.remaining_count = Set(remaining_count);
-        active.is_active = Set(is_active);
-        active.update(db).await?;
-        info!("Updated existing envelope via sync: {}", object_id);
+    match existing {
+        None => {
+            let event = find_created_event(rpc, package_id, object_id, data.previous_transaction.as_deref()).await?;
+            let Some(EnvelopeEvent::Created { data, coin_type }) = events::decode(&event, package_id)? else {
+                unreachable!("find_created_event only returns EnvelopeCreated events");
+            };
+            let coin_type = resolve_coin_type(rpc, package_id, &data, coin_type).await?;
+            process_created_event(db, network, &event, data, coin_type.clone()).await?;
+            info!("Inserted new envelope via sync: {}", object_id);
+            register_coin(db, network, rpc, &coin_type).await;
+        }
+        // Rows inserted by older versions of this sync carry the time of the sync instead.
+        Some(env) if env.tx_digest == LEGACY_SYNC_DIGEST => {
+            let event = find_created_event(rpc, package_id, object_id, data.previous_transaction.as_deref()).await?;
+            let mut active: envelopes::ActiveModel = env.into();
+            active.created_at = Set(event_time(&event));
+            active.tx_digest = Set(event.id.tx_digest);
+            active.update(db).await?;
+            info!("Restored creation time and digest of envelope {}", object_id);
+        }
+        Some(_) => {}
+    }
+
+    let claims_added = if import_history {
+        import_claims(db, network, package_id, rpc, object_id).await?
     } else {
-        envelopes::Entity::insert(model).exec(db).await?;
-        info!("Inserted new envelope via sync: {}", object_id);
+        0
+    };
+    if claims_added > 0 {
+        info!("Imported {} missing claims of envelope {}", claims_added, object_id);
     }
 
-    Ok(())
+    // Imported claims decrement `remaining_count` as they go; the object has the final word.
+    envelopes::Entity::update_many()
+        .col_expr(envelopes::Column::RemainingCount, Expr::value(chain.remaining_count))
+        .col_expr(envelopes::Column::IsActive, Expr::value(chain.is_active))
+        .filter(envelopes::Column::EnvelopeId.eq(object_id.clone()))
+        .filter(envelopes::Column::Network.eq(network))
+        .exec(db)
+        .await?;
+    info!("Updated envelope via sync: {}", object_id);
+
+    Ok(EnvelopeSync { claims_added })
+}
+
+/// Inserts every `EnvelopeClaimed` event of the envelope that isn't stored yet. Each claim
+/// mutates the envelope object, so its transactions are exactly those that changed it.
+async fn import_claims(
+    db: &DatabaseConnection,
+    network: &str,
+    package_id: &str,
+    rpc: &SuiRpcClient,
+    object_id: &ObjectId,
+) -> anyhow::Result<usize> {
+    let options = TransactionBlockResponseOptions { show_events: true, ..Default::default() };
+    let mut cursor: Option<String> = None;
+    let mut added = 0;
+
+    loop {
+        let page = rpc.query_transaction_blocks(
+            TransactionFilter::ChangedObject(object_id.clone()),
+            options,
+            cursor.as_deref(),
+            BACKFILL_PAGE_SIZE,
+            false,
+        ).await?;
+
+        for tx in page.data {
+            for mut event in tx.events.into_iter().flatten() {
+                // Other packages' events can share the transaction; `decode` passes over them.
+                let Ok(Some(EnvelopeEvent::Claimed(data))) = events::decode(&event, package_id) else {
+                    continue;
+                };
+                if &data.id != object_id {
+                    continue;
+                }
+                if event.timestamp_ms.is_none() {
+                    event.timestamp_ms = tx.timestamp_ms.clone();
+                }
+                if process_claimed_event(db, network, &event, data).await? {
+                    added += 1;
+                }
+            }
+        }
+
+        if !page.has_next_page || page.next_cursor.is_none() {
+            break;
+        }
+        cursor = page.next_cursor;
+    }
+
+    Ok(added)
+}
+
+/// The `EnvelopeCreated` event of an envelope. The object's previous transaction is its creation
+/// until the first claim; after that, the creation is the earliest transaction that changed it.
+async fn find_created_event(
+    rpc: &SuiRpcClient,
+    package_id: &str,
+    object_id: &ObjectId,
+    previous_transaction: Option<&str>,
+) -> anyhow::Result<SuiEvent> {
+    let options = TransactionBlockResponseOptions { show_events: true, ..Default::default() };
+
+    if let Some(digest) = previous_transaction {
+        let tx = rpc.get_transaction_block(digest, options).await?;
+        if let Some(event) = created_event_in(tx, package_id, object_id) {
+            return Ok(event);
+        }
+    }
+
+    let page = rpc.query_transaction_blocks(
+        TransactionFilter::ChangedObject(object_id.clone()),
+        options,
+        None,
+        1,
+        false,
+    ).await?;
+
+    page.data.into_iter()
+        .next()
+        .and_then(|tx| created_event_in(tx, package_id, object_id))
+        .ok_or_else(|| anyhow::anyhow!("No EnvelopeCreated event found for envelope {}", object_id))
 }
 
-pub async fn sync_claim_by_tx(db: &DatabaseConnection, network: &str, rpc_url: &str, tx_digest: &str) -> anyhow::Result<()> {
+fn created_event_in(tx: SuiTransactionBlockResponse, package_id: &str, object_id: &ObjectId) -> Option<SuiEvent> {
+    let mut event = tx.events?.into_iter().find(|event| matches!(
+        events::decode(event, package_id),
+        Ok(Some(EnvelopeEvent::Created { ref data, .. })) if &data.id == object_id
+    ))?;
+
+    // Events inside a transaction response don't always carry their own timestamp
+    if event.timestamp_ms.is_none() {
+        event.timestamp_ms = tx.timestamp_ms;
+    }
+    Some(event)
+}
+
+pub async fn sync_claim_by_tx(
+    db: &DatabaseConnection,
+    network: &str,
+    package_id: &str,
+    rpc: &SuiRpcClient,
+    tx_digest: &str,
+) -> anyhow::Result<()> {
     info!("Manually syncing claim for tx {} on network {}", tx_digest, network);
 
-    let client = reqwest::Client::new();
-    let query = json!({
-        "jsonrpc": "2.0",
-        "id": 1,
-        "method": "sui_getTransactionBlock",
-        "params": [
-            tx_digest,
-            { "showEvents": true }
-        ]
-    });
+    let options = TransactionBlockResponseOptions { show_events: true, ..Default::default() };
+    let tx = rpc.get_transaction_block(tx_digest, options).await?;
 
-    let res = client.post(rpc_url).json(&query).send().await?;
-    let json: serde_json::Value = res.json().await?;
-    
-    // Check for error
-    if let Some(err) = json.get("error") {
-        return Err(anyhow::anyhow!("RPC Error: {:?}", err));
-    }
-
-    let timestamp_ms = json["result"]["timestampMs"].as_str().map(|s| s.to_string());
-
-    let events = &json["result"]["events"];
-    if let Some(events_arr) = events.as_array() {
-        for event_json in events_arr {
-             let type_str = event_json["type"].as_str().unwrap_or_default();
-             
-             if type_str.contains("::EnvelopeClaimed") {
-                 let parsed_json = &event_json["parsedJson"];
-                 let env_id = parsed_json["id"].as_str().unwrap_or_default();
-                 
-                 // CHECK ENVELOPE EXISTENCE
-                 let env_exists = envelopes::Entity::find()
-                    .filter(envelopes::Column::EnvelopeId.eq(env_id))
-                    .filter(envelopes::Column::Network.eq(network))
-                    .one(db)
-                    .await?;
-                    
-                 if env_exists.is_none() {
-                     info!("Envelope {} not found for claim {}, syncing envelope first...", env_id, tx_digest);
-                     // Add delay before calling RPC again to allow indexer to catch up
-                     sleep(Duration::from_millis(500)).await;
-
-                     if let Err(e) = sync_envelope_by_id(db, network, rpc_url, env_id).await {
-                         error!("Failed to sync envelope {}: {:?}", env_id, e);
-                         // Continue to try to process claim? Or fail? 
-                         // Fail is better because constraints might fail.
-                         return Err(e);
-                     }
-                 }
-                 
-                 let event_struct = SuiEvent {
-                     id: SuiEventId { tx_digest: tx_digest.to_string() },
-                     timestamp_ms: timestamp_ms.clone(),
-                     parsed_json: parsed_json.clone(),
-                     type_: type_str.to_string(),
-                 };
-                 
-                 process_claimed_event(db, network, event_struct).await;
-                 return Ok(());
-             }
-        }
-        return Err(anyhow::anyhow!("No claim event found in transaction"));
-    } else {
-        return Err(anyhow::anyhow!("No properties found in transaction result"));
+    let events = tx.events
+        .ok_or_else(|| anyhow::anyhow!("No properties found in transaction result"))?;
+
+    for mut event in events {
+        if let Some(EnvelopeEvent::Claimed(data)) = events::decode(&event, package_id)? {
+            let env_id = data.id.clone();
+
+            // CHECK ENVELOPE EXISTENCE
+            let env_exists = envelopes::Entity::find()
+               .filter(envelopes::Column::EnvelopeId.eq(env_id.clone()))
+               .filter(envelopes::Column::Network.eq(network))
+               .one(db)
+               .await?;
+               
+            if env_exists.is_none() {
+                info!("Envelope {} not found for claim {}, syncing envelope first...", env_id, tx_digest);
+                // Add delay before calling RPC again to allow indexer to catch up
+                sleep(Duration::from_millis(500)).await;
+
+                if let Err(e) = sync_envelope_by_id(db, network, package_id, rpc, &env_id).await {
+                    error!("Failed to sync envelope {}: {:?}", env_id, e);
+                    // Continue to try to process claim? Or fail? 
+                    // Fail is better because constraints might fail.
+                    return Err(e);
+                }
+            }
+
+            // Events inside a transaction response don't always carry their own timestamp
+            if event.timestamp_ms.is_none() {
+                event.timestamp_ms = tx.timestamp_ms.clone();
+            }
+
+            process_claimed_event(db, network, &event, data).await?;
+            return Ok(());
+        }
     }
+
+    Err(anyhow::anyhow!("No claim event found in transaction"))
 }
 
-async fn update_envelope_decrement(db: &DatabaseConnection, env_id: &str, network: &str) -> Result<(), DbErr> {
-    // 1. Fetch current
-    // 2. Decrement
-    // 3. Update
-    // Note: This is racy without a proper transaction or atomic update logic, 
-    // but acceptable for this demo.
+async fn update_envelope_decrement<C: ConnectionTrait>(db: &C, env_id: &ObjectId, network: &str) -> Result<(), DbErr> {
+    // Runs in the same transaction as the claim insert, so a count is only ever
+    // decremented for a claim row that was actually written.
     
     // Using an update statement is safer for concurrency than read-modify-write in app
     // UPDATE envelopes SET remaining_count = remaining_count - 1 WHERE envelope_id = ... AND network = ...
@@ -463,7 +1191,7 @@ async fn update_envelope_decrement(db: &DatabaseConnection, env_id: &str, networ
     let stmt = Statement::from_sql_and_values(
         db.get_database_backend(),
         r#"UPDATE envelopes SET remaining_count = remaining_count - 1 WHERE envelope_id = ? AND network = ? AND remaining_count > 0"#,
-        vec![env_id.into(), network.into()],
+        vec![env_id.clone().into(), network.into()],
     );
     
     let res = db.execute(stmt).await?;
@@ -477,3 +1205,145 @@ async fn update_envelope_decrement(db: &DatabaseConnection, env_id: &str, networ
     Ok(())
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PACKAGE: &str = "0x00000000000000000000000000000000000000000000000000000000000000aa";
+
+    fn envelope_object(fields: serde_json::Value) -> SuiObjectData {
+        serde_json::from_value(serde_json::json!({
+            "objectId": "0xe1",
+            "type": "0xaa::sui_red_envelope::RedEnvelope<0x2::sui::SUI>",
+            "content": { "fields": fields },
+        })).unwrap()
+    }
+
+    #[test]
+    fn parses_envelope_counters() {
+        let chain = parse_envelope_object(&envelope_object(serde_json::json!({ "remaining_count": "2", "balance": "500" })), PACKAGE).unwrap();
+        assert_eq!((chain.remaining_count, chain.is_active), (2, true));
+
+        let chain = parse_envelope_object(&envelope_object(serde_json::json!({ "remaining_count": 2, "balance": { "fields": { "value": "0" } } })), PACKAGE).unwrap();
+        assert_eq!((chain.remaining_count, chain.is_active), (2, false));
+    }
+
+    #[test]
+    fn unreadable_counters_fail_instead_of_defaulting_to_zero() {
+        for fields in [
+            serde_json::json!({ "balance": "500" }),
+            serde_json::json!({ "remaining_count": "2" }),
+            serde_json::json!({ "remaining_count": "two", "balance": "500" }),
+            serde_json::json!({ "remaining_count": "2", "balance": { "value": "500" } }),
+            serde_json::json!({ "remaining_count": -1, "balance": "500" }),
+        ] {
+            assert!(parse_envelope_object(&envelope_object(fields.clone()), PACKAGE).is_err(), "{} should fail", fields);
+        }
+    }
+
+    const OWNER: &str = "0x00000000000000000000000000000000000000000000000000000000000000c1";
+    const ENVELOPE: &str = "0x00000000000000000000000000000000000000000000000000000000000000e1";
+    const USDC: &str = "0x00000000000000000000000000000000000000000000000000000000000000dd::usdc::USDC";
+
+    fn withdraw_call(package: &str, input: u64) -> serde_json::Value {
+        serde_json::json!({ "MoveCall": {
+            "package": package,
+            "module": "sui_red_envelope",
+            "function": "withdraw_remaining",
+            "arguments": [{ "Input": input }],
+        } })
+    }
+
+    fn ptb(transactions: Vec<serde_json::Value>) -> serde_json::Value {
+        serde_json::json!({
+            "inputs": [
+                { "type": "object", "objectId": "0xe0" },
+                { "type": "object", "objectId": ENVELOPE },
+            ],
+            "transactions": transactions,
+        })
+    }
+
+    /// A withdraw transaction in which `OWNER` got `owner_change` of `coin_type` and paid
+    /// 1000 - 300 in gas.
+    fn withdrawal(coin_type: &str, owner_change: &str) -> SuiTransactionBlockResponse {
+        serde_json::from_value(serde_json::json!({
+            "digest": "withdraw",
+            "effects": {
+                "status": { "status": "success" },
+                "gasUsed": { "computationCost": "600", "storageCost": "400", "storageRebate": "300" },
+            },
+            "balanceChanges": [
+                { "owner": { "AddressOwner": OWNER }, "coinType": coin_type, "amount": owner_change },
+                { "owner": { "AddressOwner": "0x5" }, "coinType": coin_type, "amount": "999" },
+                { "owner": { "ObjectOwner": OWNER }, "coinType": coin_type, "amount": "999" },
+            ],
+        })).unwrap()
+    }
+
+    #[test]
+    fn finds_the_withdrawn_envelope() {
+        let tx = ptb(vec![withdraw_call("0xaa", 1)]);
+        assert_eq!(withdrawn_envelope_id(&tx, PACKAGE).unwrap().as_str(), ENVELOPE);
+    }
+
+    #[test]
+    fn records_the_first_of_several_withdrawals() {
+        let tx = ptb(vec![withdraw_call(PACKAGE, 1), withdraw_call(PACKAGE, 0)]);
+        assert_eq!(withdrawn_envelope_id(&tx, PACKAGE).unwrap().as_str(), ENVELOPE);
+    }
+
+    #[test]
+    fn ignores_withdraw_calls_to_other_packages() {
+        let tx = ptb(vec![withdraw_call("0xbb", 0), withdraw_call(PACKAGE, 1)]);
+        assert_eq!(withdrawn_envelope_id(&tx, PACKAGE).unwrap().as_str(), ENVELOPE);
+
+        assert!(withdrawn_envelope_id(&ptb(vec![withdraw_call("0xbb", 1)]), PACKAGE).is_none());
+        assert!(withdrawn_envelope_id(&ptb(Vec::new()), PACKAGE).is_none());
+    }
+
+    #[test]
+    fn sui_refunds_add_back_the_gas() {
+        let owner: SuiAddress = OWNER.parse().unwrap();
+        // The owner's SUI went up by the refund minus the 700 they paid in gas.
+        let tx = withdrawal("0x2::sui::SUI", "4300");
+        assert_eq!(refunded_amount(&tx, &owner, SUI_COIN_TYPE), Decimal::from(5000));
+    }
+
+    #[test]
+    fn other_refunds_leave_the_gas_out() {
+        let owner: SuiAddress = OWNER.parse().unwrap();
+        let tx = withdrawal(USDC, "5000");
+        assert_eq!(refunded_amount(&tx, &owner, USDC), Decimal::from(5000));
+        // Only changes in the envelope's coin count.
+        assert_eq!(refunded_amount(&tx, &owner, "0x3::other::OTHER"), Decimal::ZERO);
+    }
+
+    #[test]
+    fn chain_time_falls_back_to_the_epoch() {
+        assert_eq!(chain_time(Some("1700000000000")).and_utc().timestamp(), 1_700_000_000);
+        assert_eq!(chain_time(None), chrono::NaiveDateTime::default());
+        assert_eq!(chain_time(Some("soon")), chrono::NaiveDateTime::default());
+    }
+
+    #[test]
+    fn envelope_coin_type_reads_t() {
+        let coin_type = envelope_coin_type("0xaa::sui_red_envelope::RedEnvelope<0x2::sui::SUI>", PACKAGE).unwrap();
+        assert_eq!(coin_type, SUI_COIN_TYPE);
+    }
+
+    #[test]
+    fn envelope_coin_type_rejects_other_packages_and_types() {
+        for object_type in [
+            "0xbb::sui_red_envelope::RedEnvelope<0x2::sui::SUI>",
+            "0xaa::other_module::RedEnvelope<0x2::sui::SUI>",
+            "0xaa::sui_red_envelope::Other<0x2::sui::SUI>",
+            "0xaa::sui_red_envelope::RedEnvelope",
+            "0x2::coin::Coin<0x2::sui::SUI>",
+            "",
+        ] {
+            assert!(envelope_coin_type(object_type, PACKAGE).is_err(), "{:?} should be rejected", object_type);
+        }
+    }
+}
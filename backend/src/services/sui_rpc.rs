@@ -0,0 +1,676 @@
+use std::cmp::Reverse;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use rand::Rng;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::json;
+use thiserror::Error;
+use tokio::time::sleep;
+use tracing::warn;
+
+use crate::address::{ObjectId, SuiAddress};
+
+const MAX_ATTEMPTS: usize = 4;
+const BASE_BACKOFF: Duration = Duration::from_millis(200);
+const MAX_BACKOFF: Duration = Duration::from_secs(5);
+
+const MAX_HEALTH: u32 = 100;
+const HEALTH_REWARD: u32 = 10;
+const HEALTH_PENALTY: u32 = 25;
+
+/// The most ids one `sui_multiGetObjects` call accepts.
+pub const MULTI_GET_OBJECTS_LIMIT: usize = 50;
+
+/// One HTTP connection pool for every RPC client in the process.
+fn shared_http() -> reqwest::Client {
+    static HTTP: OnceLock<reqwest::Client> = OnceLock::new();
+    HTTP.get_or_init(|| {
+        reqwest::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .expect("Failed to build HTTP client")
+    })
+    .clone()
+}
+
+#[derive(Debug, Error)]
+pub enum RpcError {
+    #[error("HTTP request failed: {0}")]
+    Transport(#[from] reqwest::Error),
+    #[error("RPC endpoint returned HTTP {0}")]
+    Status(reqwest::StatusCode),
+    #[error("RPC error {code}: {message}")]
+    Rpc { code: i64, message: String },
+    #[error("Failed to decode RPC response: {0}")]
+    Decode(#[from] serde_json::Error),
+    #[error("Object not found on chain: {0}")]
+    ObjectNotFound(String),
+}
+
+impl RpcError {
+    /// Timeouts, connection failures, 429s and 5xx are worth retrying on another endpoint.
+    fn is_retryable(&self) -> bool {
+        match self {
+            RpcError::Transport(e) => e.is_timeout() || e.is_connect() || e.is_body(),
+            RpcError::Status(status) => {
+                *status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+            }
+            _ => false,
+        }
+    }
+}
+
+/// An endpoint URL and its health score: successes raise it, retryable failures lower it, and
+/// healthier endpoints are tried first so a flaky one gets demoted.
+#[derive(Debug)]
+pub struct Endpoint {
+    url: String,
+    health: AtomicU32,
+}
+
+impl Endpoint {
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    fn health(&self) -> u32 {
+        self.health.load(Ordering::Relaxed)
+    }
+
+    pub fn record_success(&self) {
+        let _ = self.health.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |h| {
+            Some((h + HEALTH_REWARD).min(MAX_HEALTH))
+        });
+    }
+
+    pub fn record_failure(&self) {
+        let _ = self.health.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |h| {
+            Some(h.saturating_sub(HEALTH_PENALTY))
+        });
+    }
+}
+
+/// Endpoints in their configured order, all starting out fully healthy.
+#[derive(Debug)]
+struct Endpoints(Vec<Endpoint>);
+
+impl Endpoints {
+    fn new(urls: Vec<String>) -> Self {
+        Self(urls.into_iter()
+            .map(|url| Endpoint { url, health: AtomicU32::new(MAX_HEALTH) })
+            .collect())
+    }
+
+    /// Healthiest first. Stable sort: equally healthy endpoints keep their configured order.
+    fn by_health(&self) -> Vec<&Endpoint> {
+        let mut order: Vec<&Endpoint> = self.0.iter().collect();
+        order.sort_by_key(|e| Reverse(e.health()));
+        order
+    }
+
+    fn report(&self) -> Vec<EndpointHealth> {
+        self.0.iter()
+            .map(|e| EndpointHealth { url: e.url.clone(), score: e.health() })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EndpointHealth {
+    pub url: String,
+    pub score: u32,
+}
+
+/// Exponential backoff with full jitter: a random delay up to `BASE_BACKOFF * 2^attempt`.
+fn backoff(attempt: usize) -> Duration {
+    let ceiling = BASE_BACKOFF
+        .saturating_mul(1 << attempt.min(16))
+        .min(MAX_BACKOFF);
+    let millis = rand::thread_rng().gen_range(0..=ceiling.as_millis() as u64);
+    Duration::from_millis(millis)
+}
+
+/// Typed client for the Sui JSON-RPC API of one network.
+///
+/// Cheap to clone; all clones share the connection pool and endpoint health. Point it at
+/// any URL, e.g. a local stand-in server, to exercise indexer logic without a real node.
+#[derive(Debug, Clone)]
+pub struct SuiRpcClient {
+    http: reqwest::Client,
+    endpoints: Arc<Endpoints>,
+    ws_endpoints: Arc<Endpoints>,
+}
+
+impl SuiRpcClient {
+    /// `urls` are tried in this order until their health scores say otherwise, and likewise
+    /// `ws_urls` for subscriptions, which may be empty when the network is only polled.
+    pub fn new(urls: Vec<String>, ws_urls: Vec<String>) -> Self {
+        assert!(!urls.is_empty(), "SuiRpcClient needs at least one endpoint");
+
+        Self {
+            http: shared_http(),
+            endpoints: Arc::new(Endpoints::new(urls)),
+            ws_endpoints: Arc::new(Endpoints::new(ws_urls)),
+        }
+    }
+
+    pub fn endpoint_health(&self) -> Vec<EndpointHealth> {
+        self.endpoints.report()
+    }
+
+    pub fn ws_endpoint_health(&self) -> Vec<EndpointHealth> {
+        self.ws_endpoints.report()
+    }
+
+    /// The WebSocket endpoint to subscribe through next. The subscriber reports how each
+    /// connection ended, so an endpoint that keeps dropping loses its turn to the others.
+    pub fn ws_endpoint(&self) -> Option<&Endpoint> {
+        self.ws_endpoints.by_health().first().copied()
+    }
+
+    /// Sends the request to the healthiest endpoint, failing over to the next one with
+    /// backoff on retryable errors, for up to `MAX_ATTEMPTS` attempts.
+    async fn call<R: DeserializeOwned>(&self, method: &str, params: serde_json::Value) -> Result<R, RpcError> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        });
+
+        let order = self.endpoints.by_health();
+
+        let mut attempt = 0;
+        loop {
+            let endpoint = order[attempt % order.len()];
+
+            match self.send(&endpoint.url, &request).await {
+                Ok(result) => {
+                    endpoint.record_success();
+                    return Ok(result);
+                }
+                Err(e) if e.is_retryable() => {
+                    endpoint.record_failure();
+                    attempt += 1;
+                    if attempt >= MAX_ATTEMPTS {
+                        return Err(e);
+                    }
+                    warn!(
+                        "{} via {} failed: {}, retrying ({}/{})",
+                        method, endpoint.url, e, attempt, MAX_ATTEMPTS - 1
+                    );
+                    sleep(backoff(attempt)).await;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    async fn send<R: DeserializeOwned>(&self, url: &str, request: &serde_json::Value) -> Result<R, RpcError> {
+        let res = self.http.post(url).json(request).send().await?;
+        if !res.status().is_success() {
+            return Err(RpcError::Status(res.status()));
+        }
+
+        let body: JsonRpcResponse<R> = serde_json::from_slice(&res.bytes().await?)?;
+        match (body.result, body.error) {
+            (_, Some(err)) => Err(RpcError::Rpc { code: err.code, message: err.message }),
+            (Some(result), None) => Ok(result),
+            // `null` results (e.g. unknown coin metadata) deserialize through `Option<R>`.
+            (None, None) => Ok(serde_json::from_value(serde_json::Value::Null)?),
+        }
+    }
+
+    pub async fn get_object(&self, object_id: &ObjectId, options: ObjectDataOptions) -> Result<SuiObjectResponse, RpcError> {
+        self.call("sui_getObject", json!([object_id, options])).await
+    }
+
+    /// Like [`Self::get_object`], but fails with [`RpcError::ObjectNotFound`] when the object has no data.
+    pub async fn get_object_data(&self, object_id: &ObjectId, options: ObjectDataOptions) -> Result<SuiObjectData, RpcError> {
+        self.get_object(object_id, options)
+            .await?
+            .data
+            .ok_or_else(|| RpcError::ObjectNotFound(object_id.to_string()))
+    }
+
+    /// Responses come back in the order of `object_ids`, at most [`MULTI_GET_OBJECTS_LIMIT`] of them.
+    pub async fn multi_get_objects(&self, object_ids: &[ObjectId], options: ObjectDataOptions) -> Result<Vec<SuiObjectResponse>, RpcError> {
+        self.call("sui_multiGetObjects", json!([object_ids, options])).await
+    }
+
+    pub async fn get_transaction_block(
+        &self,
+        digest: &str,
+        options: TransactionBlockResponseOptions,
+    ) -> Result<SuiTransactionBlockResponse, RpcError> {
+        self.call("sui_getTransactionBlock", json!([digest, options])).await
+    }
+
+    pub async fn query_events(
+        &self,
+        filter: EventFilter,
+        cursor: Option<&EventId>,
+        limit: u64,
+        descending: bool,
+    ) -> Result<Page<SuiEvent, EventId>, RpcError> {
+        self.call("suix_queryEvents", json!([filter, cursor, limit, descending])).await
+    }
+
+    pub async fn query_transaction_blocks(
+        &self,
+        filter: TransactionFilter,
+        options: TransactionBlockResponseOptions,
+        cursor: Option<&str>,
+        limit: u64,
+        descending: bool,
+    ) -> Result<Page<SuiTransactionBlockResponse, String>, RpcError> {
+        let query = json!({ "filter": filter, "options": options });
+        self.call("suix_queryTransactionBlocks", json!([query, cursor, limit, descending])).await
+    }
+
+    /// `None` when the coin type has no published metadata.
+    pub async fn get_coin_metadata(&self, coin_type: &str) -> Result<Option<SuiCoinMetadata>, RpcError> {
+        self.call("suix_getCoinMetadata", json!([coin_type])).await
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonRpcResponse<R> {
+    result: Option<R>,
+    error: Option<JsonRpcError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonRpcError {
+    code: i64,
+    message: String,
+}
+
+/// Deserializes a Move `u64`, which JSON-RPC renders as a string (and small integers sometimes as numbers).
+pub fn u64_from_str<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StrOrNum {
+        Str(String),
+        Num(u64),
+    }
+
+    match StrOrNum::deserialize(deserializer)? {
+        StrOrNum::Str(s) => s.parse().map_err(serde::de::Error::custom),
+        StrOrNum::Num(n) => Ok(n),
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ObjectDataOptions {
+    pub show_type: bool,
+    pub show_content: bool,
+    pub show_previous_transaction: bool,
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionBlockResponseOptions {
+    pub show_input: bool,
+    pub show_effects: bool,
+    pub show_events: bool,
+    pub show_balance_changes: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub enum EventFilter {
+    MoveModule { package: String, module: String },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub enum TransactionFilter {
+    MoveFunction {
+        package: String,
+        module: Option<String>,
+        function: Option<String>,
+    },
+    /// Transactions that created, mutated or unwrapped the object.
+    ChangedObject(ObjectId),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Page<T, C> {
+    pub data: Vec<T>,
+    pub next_cursor: Option<C>,
+    pub has_next_page: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub struct EventId {
+    pub tx_digest: String,
+    pub event_seq: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SuiEvent {
+    pub id: EventId,
+    pub timestamp_ms: Option<String>,
+    pub parsed_json: serde_json::Value,
+    #[serde(rename = "type")]
+    pub type_: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SuiObjectResponse {
+    pub data: Option<SuiObjectData>,
+    pub error: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SuiObjectData {
+    pub object_id: ObjectId,
+    #[serde(rename = "type")]
+    pub type_: Option<String>,
+    pub content: Option<SuiParsedData>,
+    /// Digest of the last transaction that wrote the object.
+    pub previous_transaction: Option<String>,
+}
+
+/// `content` of an object; `fields` is shaped by the Move struct.
+#[derive(Debug, Deserialize)]
+pub struct SuiParsedData {
+    #[serde(default)]
+    pub fields: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SuiTransactionBlockResponse {
+    pub digest: String,
+    pub timestamp_ms: Option<String>,
+    pub transaction: Option<SuiTransactionBlock>,
+    pub effects: Option<SuiTransactionBlockEffects>,
+    pub events: Option<Vec<SuiEvent>>,
+    pub balance_changes: Option<Vec<BalanceChange>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SuiTransactionBlock {
+    pub data: SuiTransactionBlockData,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SuiTransactionBlockData {
+    pub sender: SuiAddress,
+    /// Transaction kind; for programmable transactions this holds `inputs` and `transactions`.
+    pub transaction: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SuiTransactionBlockEffects {
+    pub status: ExecutionStatus,
+    pub gas_used: GasCostSummary,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExecutionStatus {
+    pub status: String,
+    pub error: Option<String>,
+}
+
+impl ExecutionStatus {
+    pub fn is_success(&self) -> bool {
+        self.status == "success"
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GasCostSummary {
+    #[serde(deserialize_with = "u64_from_str")]
+    pub computation_cost: u64,
+    #[serde(deserialize_with = "u64_from_str")]
+    pub storage_cost: u64,
+    #[serde(deserialize_with = "u64_from_str")]
+    pub storage_rebate: u64,
+}
+
+impl GasCostSummary {
+    /// What the gas payer was charged; negative when the rebate exceeds the costs.
+    pub fn net_cost(&self) -> i128 {
+        self.computation_cost as i128 + self.storage_cost as i128 - self.storage_rebate as i128
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BalanceChange {
+    /// `{ "AddressOwner": "0x.." }`, `{ "ObjectOwner": "0x.." }`, `"Immutable"`, ...
+    pub owner: serde_json::Value,
+    pub coin_type: String,
+    /// Signed amount in base units.
+    pub amount: String,
+}
+
+impl BalanceChange {
+    pub fn address_owner(&self) -> Option<SuiAddress> {
+        self.owner.get("AddressOwner").and_then(|o| o.as_str()).and_then(|o| o.parse().ok())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SuiCoinMetadata {
+    pub decimals: u8,
+    pub name: String,
+    pub symbol: String,
+    pub description: String,
+    pub icon_url: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use axum::{http::StatusCode, routing::post, Json, Router};
+    use serde_json::Value;
+
+    /// Serves `respond` on a local port as a stand-in RPC node and returns its URL. `respond`
+    /// gets the JSON-RPC request and returns the HTTP status and body to answer with.
+    async fn stand_in<F>(respond: F) -> String
+    where
+        F: Fn(Value) -> (StatusCode, Value) + Clone + Send + Sync + 'static,
+    {
+        let app = Router::new().route("/", post(move |Json(request): Json<Value>| async move {
+            let (status, body) = respond(request);
+            (status, Json(body))
+        }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        url
+    }
+
+    fn result(result: Value) -> (StatusCode, Value) {
+        (StatusCode::OK, json!({ "jsonrpc": "2.0", "id": 1, "result": result }))
+    }
+
+    fn filter() -> EventFilter {
+        EventFilter::MoveModule { package: "0x1".to_string(), module: "m".to_string() }
+    }
+
+    #[tokio::test]
+    async fn decodes_an_event_page() {
+        let url = stand_in(|request| {
+            assert_eq!(request["method"], "suix_queryEvents");
+            assert_eq!(request["params"][1], json!({ "txDigest": "prev", "eventSeq": "3" }));
+            assert_eq!(request["params"][2], 2);
+            result(json!({
+                "data": [{
+                    "id": { "txDigest": "abc", "eventSeq": "0" },
+                    "timestampMs": "1700000000000",
+                    "parsedJson": { "amount": "10" },
+                    "type": "0x1::m::EnvelopeClaimed",
+                    "packageId": "0x1",
+                    "sender": "0x2"
+                }],
+                "nextCursor": { "txDigest": "abc", "eventSeq": "0" },
+                "hasNextPage": true
+            }))
+        }).await;
+
+        let cursor = EventId { tx_digest: "prev".to_string(), event_seq: "3".to_string() };
+        let page = SuiRpcClient::new(vec![url], Vec::new())
+            .query_events(filter(), Some(&cursor), 2, false)
+            .await
+            .unwrap();
+
+        assert!(page.has_next_page);
+        assert_eq!(page.data.len(), 1);
+        let event = &page.data[0];
+        assert_eq!(event.id, EventId { tx_digest: "abc".to_string(), event_seq: "0".to_string() });
+        assert_eq!(event.timestamp_ms.as_deref(), Some("1700000000000"));
+        assert_eq!(event.parsed_json["amount"], "10");
+        assert_eq!(event.type_, "0x1::m::EnvelopeClaimed");
+        assert_eq!(page.next_cursor, Some(event.id.clone()));
+    }
+
+    #[tokio::test]
+    async fn null_coin_metadata_is_none() {
+        let url = stand_in(|_| result(Value::Null)).await;
+        let metadata = SuiRpcClient::new(vec![url], Vec::new()).get_coin_metadata("0x1::c::C").await.unwrap();
+        assert!(metadata.is_none());
+    }
+
+    #[tokio::test]
+    async fn decodes_coin_metadata() {
+        let url = stand_in(|_| result(json!({
+            "decimals": 9,
+            "name": "Sui",
+            "symbol": "SUI",
+            "description": "",
+            "iconUrl": null,
+            "id": "0x3"
+        }))).await;
+
+        let metadata = SuiRpcClient::new(vec![url], Vec::new()).get_coin_metadata("0x2::sui::SUI").await.unwrap().unwrap();
+        assert_eq!(metadata.decimals, 9);
+        assert_eq!(metadata.symbol, "SUI");
+        assert_eq!(metadata.icon_url, None);
+    }
+
+    #[tokio::test]
+    async fn maps_json_rpc_errors() {
+        let url = stand_in(|_| (StatusCode::OK, json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "error": { "code": -32602, "message": "Invalid params" }
+        }))).await;
+
+        let err = SuiRpcClient::new(vec![url], Vec::new()).get_coin_metadata("nonsense").await.unwrap_err();
+        match err {
+            RpcError::Rpc { code, message } => {
+                assert_eq!(code, -32602);
+                assert_eq!(message, "Invalid params");
+            }
+            other => panic!("expected an RPC error, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn undecodable_results_are_decode_errors() {
+        let url = stand_in(|_| result(json!({ "data": "not a list" }))).await;
+        let err = SuiRpcClient::new(vec![url], Vec::new()).query_events(filter(), None, 1, false).await.unwrap_err();
+        assert!(matches!(err, RpcError::Decode(_)), "got {:?}", err);
+    }
+
+    /// A stand-in that answers with `statuses` in turn, then with an empty result, and counts
+    /// the requests it got.
+    async fn flaky(statuses: &'static [StatusCode]) -> (String, Arc<AtomicUsize>) {
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        let url = stand_in(move |_| {
+            let n = counter.fetch_add(1, Ordering::SeqCst);
+            match statuses.get(n) {
+                Some(status) => (*status, json!({})),
+                None => result(Value::Null),
+            }
+        }).await;
+        (url, hits)
+    }
+
+    fn scores(client: &SuiRpcClient) -> Vec<u32> {
+        client.endpoint_health().iter().map(|e| e.score).collect()
+    }
+
+    #[tokio::test]
+    async fn retries_rate_limits_and_server_errors() {
+        let (url, hits) = flaky(&[StatusCode::TOO_MANY_REQUESTS, StatusCode::BAD_GATEWAY]).await;
+        let client = SuiRpcClient::new(vec![url], Vec::new());
+
+        assert!(client.get_coin_metadata("0x2::sui::SUI").await.unwrap().is_none());
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+        assert_eq!(scores(&client), [MAX_HEALTH - 2 * HEALTH_PENALTY + HEALTH_REWARD]);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_attempts() {
+        let (url, hits) = flaky(&[StatusCode::SERVICE_UNAVAILABLE; MAX_ATTEMPTS]).await;
+        let client = SuiRpcClient::new(vec![url], Vec::new());
+
+        let err = client.get_coin_metadata("0x2::sui::SUI").await.unwrap_err();
+        assert!(matches!(err, RpcError::Status(s) if s.as_u16() == 503), "got {:?}", err);
+        assert_eq!(hits.load(Ordering::SeqCst), MAX_ATTEMPTS);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_client_errors() {
+        let (url, hits) = flaky(&[StatusCode::BAD_REQUEST]).await;
+        let client = SuiRpcClient::new(vec![url], Vec::new());
+
+        let err = client.get_coin_metadata("0x2::sui::SUI").await.unwrap_err();
+        assert!(matches!(err, RpcError::Status(s) if s.as_u16() == 400), "got {:?}", err);
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+        assert_eq!(scores(&client), [MAX_HEALTH]);
+    }
+
+    #[tokio::test]
+    async fn demotes_a_failing_endpoint() {
+        let (down, down_hits) = flaky(&[StatusCode::INTERNAL_SERVER_ERROR; 100]).await;
+        let (up, up_hits) = flaky(&[]).await;
+        let client = SuiRpcClient::new(vec![down, up], Vec::new());
+
+        // Configured order first: the failing endpoint is tried, then the call fails over.
+        client.get_coin_metadata("0x2::sui::SUI").await.unwrap();
+        assert_eq!((down_hits.load(Ordering::SeqCst), up_hits.load(Ordering::SeqCst)), (1, 1));
+        assert_eq!(scores(&client), [MAX_HEALTH - HEALTH_PENALTY, MAX_HEALTH]);
+
+        // Now the healthy endpoint goes first and the failing one isn't touched.
+        client.get_coin_metadata("0x2::sui::SUI").await.unwrap();
+        assert_eq!((down_hits.load(Ordering::SeqCst), up_hits.load(Ordering::SeqCst)), (1, 2));
+    }
+
+    #[test]
+    fn ws_endpoints_are_picked_by_health() {
+        let client = SuiRpcClient::new(vec!["http://rpc".to_string()], vec!["ws://a".to_string(), "ws://b".to_string()]);
+        assert_eq!(client.ws_endpoint().unwrap().url(), "ws://a");
+
+        client.ws_endpoint().unwrap().record_failure();
+        assert_eq!(client.ws_endpoint().unwrap().url(), "ws://b");
+
+        // Equally unhealthy: back to the configured order.
+        client.ws_endpoint().unwrap().record_failure();
+        assert_eq!(client.ws_endpoint().unwrap().url(), "ws://a");
+
+        let health: Vec<u32> = client.ws_endpoint_health().iter().map(|e| e.score).collect();
+        assert_eq!(health, [MAX_HEALTH - HEALTH_PENALTY; 2]);
+    }
+
+    #[test]
+    fn ws_endpoints_are_optional() {
+        assert!(SuiRpcClient::new(vec!["http://rpc".to_string()], Vec::new()).ws_endpoint().is_none());
+    }
+}
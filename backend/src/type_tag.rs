@@ -0,0 +1,299 @@
+//! Move type tags such as `0x2::coin::Coin<0x2::sui::SUI>`.
+//!
+//! The RPC prints framework addresses in their short form (`0x2`) and everything else in
+//! full, so the same coin can be spelled two ways. Parsing and re-printing a tag gives the
+//! canonical spelling, with every address in its 64-hex long form.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer};
+
+use crate::address;
+
+/// How deeply `vector<…>` and type parameters may nest. Real coin types stay a few levels deep;
+/// the limit keeps a hostile query string from overflowing the stack.
+const MAX_DEPTH: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TypeTag {
+    Bool,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    U256,
+    Address,
+    Signer,
+    Vector(Box<TypeTag>),
+    Struct(StructTag),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StructTag {
+    /// Always the long form.
+    pub address: String,
+    pub module: String,
+    pub name: String,
+    pub type_params: Vec<TypeTag>,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("Invalid Move type {input:?}: {reason}")]
+pub struct TypeTagError {
+    input: String,
+    reason: String,
+}
+
+impl FromStr for TypeTag {
+    type Err = TypeTagError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser { input: s, pos: 0, depth: 0 };
+        let tag = parser.type_tag().and_then(|tag| {
+            parser.skip_whitespace();
+            match parser.peek() {
+                None => Ok(tag),
+                Some(c) => Err(format!("unexpected {:?} at {}", c, parser.pos)),
+            }
+        });
+        tag.map_err(|reason| TypeTagError { input: s.to_string(), reason })
+    }
+}
+
+impl FromStr for StructTag {
+    type Err = TypeTagError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.parse::<TypeTag>()? {
+            TypeTag::Struct(tag) => Ok(tag),
+            _ => Err(TypeTagError { input: s.to_string(), reason: "not a struct type".to_string() }),
+        }
+    }
+}
+
+impl fmt::Display for TypeTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bool => f.write_str("bool"),
+            Self::U8 => f.write_str("u8"),
+            Self::U16 => f.write_str("u16"),
+            Self::U32 => f.write_str("u32"),
+            Self::U64 => f.write_str("u64"),
+            Self::U128 => f.write_str("u128"),
+            Self::U256 => f.write_str("u256"),
+            Self::Address => f.write_str("address"),
+            Self::Signer => f.write_str("signer"),
+            Self::Vector(inner) => write!(f, "vector<{}>", inner),
+            Self::Struct(tag) => tag.fmt(f),
+        }
+    }
+}
+
+impl fmt::Display for StructTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}::{}::{}", self.address, self.module, self.name)?;
+        if let Some((first, rest)) = self.type_params.split_first() {
+            write!(f, "<{}", first)?;
+            for param in rest {
+                write!(f, ", {}", param)?;
+            }
+            f.write_str(">")?;
+        }
+        Ok(())
+    }
+}
+
+/// Accepts either spelling in query strings and bodies.
+impl<'de> Deserialize<'de> for TypeTag {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+    /// Type tags currently being parsed, the one at `pos` included.
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek().filter(|c| c.is_whitespace()) {
+            self.pos += c.len_utf8();
+        }
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+        if self.input[self.pos..].starts_with(token) {
+            self.pos += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), String> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(format!("expected {:?} at {}", token, self.pos))
+        }
+    }
+
+    /// An identifier or a hex literal.
+    fn word(&mut self) -> Result<&str, String> {
+        self.skip_whitespace();
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_alphanumeric() || c == '_') {
+            self.pos += 1;
+        }
+        if self.pos == start {
+            return Err(format!("expected a name at {}", start));
+        }
+        Ok(&self.input[start..self.pos])
+    }
+
+    fn type_tag(&mut self) -> Result<TypeTag, String> {
+        if self.depth == MAX_DEPTH {
+            return Err(format!("nested more than {} deep at {}", MAX_DEPTH, self.pos));
+        }
+        self.depth += 1;
+        let tag = self.type_tag_inner();
+        self.depth -= 1;
+        tag
+    }
+
+    fn type_tag_inner(&mut self) -> Result<TypeTag, String> {
+        let word = self.word()?;
+        let tag = match word {
+            "bool" => TypeTag::Bool,
+            "u8" => TypeTag::U8,
+            "u16" => TypeTag::U16,
+            "u32" => TypeTag::U32,
+            "u64" => TypeTag::U64,
+            "u128" => TypeTag::U128,
+            "u256" => TypeTag::U256,
+            "address" => TypeTag::Address,
+            "signer" => TypeTag::Signer,
+            "vector" => {
+                self.expect("<")?;
+                let inner = self.type_tag()?;
+                self.expect(">")?;
+                TypeTag::Vector(Box::new(inner))
+            }
+            address => {
+                let address = address::normalize(address).map_err(|e| e.to_string())?;
+                self.expect("::")?;
+                let module = self.word()?.to_string();
+                self.expect("::")?;
+                let name = self.word()?.to_string();
+
+                let mut type_params = Vec::new();
+                if self.eat("<") {
+                    loop {
+                        type_params.push(self.type_tag()?);
+                        if self.eat(">") {
+                            break;
+                        }
+                        self.expect(",")?;
+                    }
+                }
+                TypeTag::Struct(StructTag { address, module, name, type_params })
+            }
+        };
+        Ok(tag)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SUI: &str = "0x0000000000000000000000000000000000000000000000000000000000000002::sui::SUI";
+
+    fn canonical(s: &str) -> String {
+        s.parse::<TypeTag>().unwrap().to_string()
+    }
+
+    #[test]
+    fn short_and_long_addresses_are_the_same_type() {
+        assert_eq!(canonical("0x2::sui::SUI"), SUI);
+        assert_eq!(canonical(SUI), SUI);
+        assert_eq!("0x2::sui::SUI".parse::<TypeTag>().unwrap(), SUI.parse::<TypeTag>().unwrap());
+    }
+
+    #[test]
+    fn parses_nested_generics() {
+        let tag: StructTag = "0xabc::pool::Pool<0x2::coin::Coin<0x2::sui::SUI>, u64>".parse().unwrap();
+        assert_eq!(tag.name, "Pool");
+        assert_eq!(tag.type_params.len(), 2);
+        assert_eq!(tag.type_params[1], TypeTag::U64);
+
+        let TypeTag::Struct(coin) = &tag.type_params[0] else { panic!("expected a struct") };
+        assert_eq!(coin.name, "Coin");
+        assert_eq!(coin.type_params[0].to_string(), SUI);
+    }
+
+    #[test]
+    fn parses_vectors() {
+        assert_eq!(canonical("vector<u8>"), "vector<u8>");
+        assert_eq!(canonical("vector<vector<0x2::sui::SUI>>"), format!("vector<vector<{}>>", SUI));
+        assert!("vector<u8".parse::<TypeTag>().is_err());
+        assert!("vector".parse::<TypeTag>().is_err());
+    }
+
+    #[test]
+    fn ignores_whitespace_between_tokens() {
+        assert_eq!(canonical(" 0x2 :: sui :: SUI "), SUI);
+        assert_eq!(canonical("0x2::a::B <u64 ,\tbool>"), format!("{}<u64, bool>", SUI.replace("::sui::SUI", "::a::B")));
+        assert_eq!(canonical("\u{3000}0x2::sui::SUI\u{a0}"), SUI);
+    }
+
+    #[test]
+    fn rejects_trailing_input() {
+        assert!("0x2::sui::SUI>".parse::<TypeTag>().is_err());
+        assert!("0x2::sui::SUI extra".parse::<TypeTag>().is_err());
+        assert!("u64 u64".parse::<TypeTag>().is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_types() {
+        for input in ["", "sui::SUI", "0x2::sui", "0x2::sui::SUI<", "0x2::a::B<>", "0x2::a::B<u8,>", "0xzz::a::B"] {
+            assert!(input.parse::<TypeTag>().is_err(), "{:?} should not parse", input);
+        }
+    }
+
+    #[test]
+    fn rejects_deep_nesting() {
+        let nested = |depth: usize| format!("{}u8{}", "vector<".repeat(depth), ">".repeat(depth));
+        assert!(nested(MAX_DEPTH - 1).parse::<TypeTag>().is_ok());
+        assert!(nested(MAX_DEPTH).parse::<TypeTag>().is_err());
+        assert!(nested(100_000).parse::<TypeTag>().is_err());
+
+        let params = |depth: usize| format!("{}u8{}", "0x2::a::B<".repeat(depth), ">".repeat(depth));
+        assert!(params(MAX_DEPTH - 1).parse::<TypeTag>().is_ok());
+        assert!(params(100_000).parse::<TypeTag>().is_err());
+    }
+
+    #[test]
+    fn rejects_non_ascii_without_panicking() {
+        for input in ["0x2::süi::SUI", "0x2::sui::SUI\u{3000}x", "é", "0x2::a::B<ü>", "\u{3000}"] {
+            assert!(input.parse::<TypeTag>().is_err(), "{:?} should not parse", input);
+        }
+    }
+
+    #[test]
+    fn struct_tag_rejects_primitives() {
+        assert!("u64".parse::<StructTag>().is_err());
+        assert!("vector<u8>".parse::<StructTag>().is_err());
+    }
+}
@@ -57,16 +57,22 @@ export const formatAmount = (amount: bigint | number | string, decimals: number)
     return `${integerPart}.${fracStr}`;
 };
 
+// The backend returns coin types with long-form addresses (0x000...0002::sui::SUI),
+// so compare with every address padded to 64 hex digits
+const normalizeCoinType = (type: string): string =>
+    type.replace(/0x([0-9a-fA-F]{1,64})(?=::)/g, (_, hex: string) => '0x' + hex.toLowerCase().padStart(64, '0'));
+
 export const getCoinConfig = (type: string): CoinConfig | undefined => {
     // Try exact match
-    let config = COIN_OPTIONS.find(c => c.type === type);
+    const normalized = normalizeCoinType(type);
+    let config = COIN_OPTIONS.find(c => normalizeCoinType(c.type) === normalized);
     if (config) return config;
 
     // Try detecting nested type (e.g. Coin<T>)
     // If incoming type is 0x2::coin::Coin<T>, we match T
-    const match = type.match(/<(.+)>/);
+    const match = normalized.match(/<(.+)>/);
     if (match) {
-        return COIN_OPTIONS.find(c => c.type === match[1]);
+        return COIN_OPTIONS.find(c => normalizeCoinType(c.type) === match[1]);
     }
     
     return undefined;
@@ -4,7 +4,7 @@ import { useCurrentAccount } from '@mysten/dapp-kit';
 import { BACKEND_URL, NETWORK, formatAmount, getCoinConfig } from '../constants';
 import { formatDate } from '../utils/fmt';
 import { ArrowUpRight, Copy, RotateCcw } from 'lucide-react';
-import { useQuery, useQueryClient } from '@tanstack/react-query';
+import { useInfiniteQuery, useQueryClient } from '@tanstack/react-query';
 import { useContract } from '../hooks/useContract';
 
 export const Dashboard: React.FC = () => {
@@ -13,16 +13,20 @@ export const Dashboard: React.FC = () => {
     const queryClient = useQueryClient();
     const [view, setView] = React.useState<'created' | 'claimed'>('created');
 
-    const { data: envelopes, isPending, error } = useQuery({
+    const { data, isPending, error, hasNextPage, fetchNextPage, isFetchingNextPage } = useInfiniteQuery({
         queryKey: ['envelopes', view, account?.address, NETWORK],
-        queryFn: async () => {
+        queryFn: async ({ pageParam }) => {
             const endpoint = view === 'created' ? 'created' : 'claimed';
-            const res = await fetch(`${BACKEND_URL}/api/envelopes/${endpoint}?address=${account?.address}&network=${NETWORK}`);
+            const cursor = pageParam ? `&cursor=${encodeURIComponent(pageParam)}` : '';
+            const res = await fetch(`${BACKEND_URL}/api/envelopes/${endpoint}?address=${account?.address}&network=${NETWORK}${cursor}`);
             if (!res.ok) throw new Error('Failed to fetch data');
             return res.json();
         },
+        initialPageParam: null as string | null,
+        getNextPageParam: (lastPage) => lastPage.next_cursor ?? null,
         enabled: !!account,
     });
+    const envelopes = data?.pages.flatMap((page) => page.items);
 
     const handleRecycle = (envelopeId: string, coinType: string) => {
         if (!confirm('Are you sure you want to withdraw the remaining balance?')) return;
@@ -183,6 +187,17 @@ export const Dashboard: React.FC = () => {
                         )}
                     </tbody>
                 </table>
+                {hasNextPage && (
+                    <div className="p-4 border-t border-slate-700/50 text-center">
+                        <button
+                            onClick={() => fetchNextPage()}
+                            disabled={isFetchingNextPage}
+                            className="px-6 py-2 bg-slate-800 hover:bg-slate-700 disabled:opacity-50 text-white text-xs font-bold rounded-xl transition-all"
+                        >
+                            {isFetchingNextPage ? 'Loading...' : 'Load more'}
+                        </button>
+                    </div>
+                )}
             </div>
         </div>
     );
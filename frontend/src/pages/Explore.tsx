@@ -1,20 +1,24 @@
 import React from 'react';
 import { useNavigate } from 'react-router-dom';
-import { useQuery } from '@tanstack/react-query';
+import { useInfiniteQuery } from '@tanstack/react-query';
 import { Gift, Loader2, Disc as Discord } from 'lucide-react';
 import { BACKEND_URL, getCoinConfig, formatAmount, NETWORK } from '../constants';
 
 export const ExplorePage: React.FC = () => {
     const navigate = useNavigate();
 
-    const { data: activeEnvelopes, isPending: isLoading } = useQuery({
+    const { data, isPending: isLoading, hasNextPage, fetchNextPage, isFetchingNextPage } = useInfiniteQuery({
         queryKey: ['activeEnvelopes', NETWORK],
-        queryFn: async () => {
-            const res = await fetch(`${BACKEND_URL}/api/envelopes/active?network=${NETWORK}`);
+        queryFn: async ({ pageParam }) => {
+            const cursor = pageParam ? `&cursor=${encodeURIComponent(pageParam)}` : '';
+            const res = await fetch(`${BACKEND_URL}/api/envelopes/active?network=${NETWORK}${cursor}`);
             if (!res.ok) throw new Error('Failed to fetch active envelopes');
             return res.json();
         },
+        initialPageParam: null as string | null,
+        getNextPageParam: (lastPage) => lastPage.next_cursor ?? null,
     });
+    const activeEnvelopes = data?.pages.flatMap((page) => page.items);
 
     return (
         <div className="pt-24 pb-12 px-4 max-w-4xl mx-auto flex flex-col items-center">
@@ -92,6 +96,15 @@ export const ExplorePage: React.FC = () => {
                                 </div>
                              )
                         })}
+                        {hasNextPage && (
+                            <button
+                                onClick={() => fetchNextPage()}
+                                disabled={isFetchingNextPage}
+                                className="mx-auto px-6 py-2 bg-slate-800 hover:bg-slate-700 disabled:opacity-50 text-white text-xs font-bold rounded-xl transition-all"
+                            >
+                                {isFetchingNextPage ? 'Loading...' : 'Load more'}
+                            </button>
+                        )}
                     </div>
                 ) : (
                     <div className="text-center py-20 glass-card rounded-3xl p-8 border-dashed border-slate-800">
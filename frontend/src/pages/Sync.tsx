@@ -29,7 +29,9 @@ export const SyncPage: React.FC = () => {
             }
 
             setStatus('success');
-            setMessage('Envelope successfully synced from chain!');
+            setMessage(data.claims_added > 0
+                ? `Envelope successfully synced from chain! Imported ${data.claims_added} missing claim(s).`
+                : 'Envelope successfully synced from chain!');
         } catch (err: any) {
             setStatus('error');
             setMessage(err.message || 'Failed to sync with chain. Make sure ID is correct.');